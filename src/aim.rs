@@ -0,0 +1,251 @@
+use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+use bevy::window::{PrimaryWindow, ReceivedCharacter};
+
+use crate::controls::{aim_mode_is, AimMode};
+use crate::game::{Action, AngleSpeed, InGameplaySet};
+use crate::prelude::*;
+
+// the throw indicator is 60px long at a speed of 30, so dragging matches the arrow
+const DRAG_PIXELS_PER_SPEED: f32 = 2.0;
+// make the gorilla a little easier to grab
+const GRAB_MARGIN: f32 = 8.0;
+// how far the mouse has to move from where it grabbed the gorilla before letting go
+// throws, so a click is not a throw
+const MIN_DRAG_PULL: f32 = 20.0;
+// every value that can be typed fits in 3 digits
+const MAX_TYPED_DIGITS: usize = 3;
+
+#[derive(Resource, Default)]
+struct DragState {
+    /// where the gorilla was grabbed, `None` when it is not being dragged
+    pressed_at: Option<Vec2>,
+    /// pulled far enough from where it was grabbed to throw when let go
+    pulled: bool,
+}
+
+pub(crate) struct DragAimPlugin;
+impl Plugin for DragAimPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DragState>()
+            .add_systems(OnEnter(Action::Enter), reset_drag)
+            .add_systems(OnEnter(Action::Planning), reset_drag)
+            .add_systems(
+                Update,
+                drag_to_aim
+                    .run_if(aim_mode_is(AimMode::Arrows))
+                    .in_set(InGameplaySet::Gorillas),
+            );
+    }
+}
+
+/// a drag still held when the turn changes does not carry over to the next one
+fn reset_drag(mut drag_state: ResMut<DragState>) {
+    *drag_state = DragState::default();
+}
+
+fn drag_to_aim(
+    player: Res<State<Player>>,
+    mouse_input: Res<ButtonInput<MouseButton>>,
+    window_query: Query<&Window, With<PrimaryWindow>>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    mut gorilla_query: Query<(&Gorilla, &Transform, &mut AngleSpeed)>,
+    mut drag_state: ResMut<DragState>,
+    mut throw_events: EventWriter<ThrowBananaEvent>,
+) {
+    let Some((g, t, mut a)) = gorilla_query
+        .iter_mut()
+        .find(|(g, _, _)| &g.player == player.get())
+    else {
+        return;
+    };
+    let cursor = cursor_world_position(&window_query, &camera_query);
+
+    if mouse_input.just_pressed(MouseButton::Left) {
+        drag_state.pulled = false;
+        drag_state.pressed_at = cursor.filter(|c| {
+            Aabb2d::new(
+                t.translation.truncate(),
+                t.scale.truncate() / 2.0 + GRAB_MARGIN,
+            )
+            .intersects(&Aabb2d::new(*c, Vec2::ZERO))
+        });
+    }
+    let Some(pressed_at) = drag_state.pressed_at else {
+        return;
+    };
+
+    if let Some(c) = cursor {
+        // a click anywhere on the gorilla is not a pull, only moving away from it is
+        if c.distance(pressed_at) >= MIN_DRAG_PULL {
+            drag_state.pulled = true;
+        }
+        // like a slingshot, pulling back throws the other way
+        let pull = t.translation.truncate() - c;
+        if drag_state.pulled && pull != Vec2::ZERO {
+            *a = drag_angle_speed(&g.player, pull);
+        }
+    }
+    if mouse_input.just_released(MouseButton::Left) {
+        drag_state.pressed_at = None;
+        if drag_state.pulled {
+            info!("drag released, throwing for {:?}", g.player);
+            throw_events.send(ThrowBananaEvent);
+        } else {
+            info!("drag too short to throw for {:?}", g.player);
+        }
+    }
+}
+
+fn cursor_world_position(
+    window_query: &Query<&Window, With<PrimaryWindow>>,
+    camera_query: &Query<(&Camera, &GlobalTransform)>,
+) -> Option<Vec2> {
+    let cursor = window_query.get_single().ok()?.cursor_position()?;
+    let (camera, camera_transform) = camera_query.get_single().ok()?;
+    camera.viewport_to_world_2d(camera_transform, cursor)
+}
+
+fn drag_angle_speed(player: &Player, pull: Vec2) -> AngleSpeed {
+    let degrees = pull.y.atan2(pull.x).to_degrees();
+    // player 2 throws to the left so their angles are mirrored
+    let degrees = match player {
+        Player::One => degrees,
+        Player::Two => 180.0 - degrees,
+    };
+    // keep the angles that point down and behind the gorilla next to 0 and MAX_ANGLE
+    let low = AngleSpeed::MAX_ANGLE as f32 - 360.0;
+    let degrees = (degrees - low).rem_euclid(360.0) + low;
+    let speed = pull.length() / DRAG_PIXELS_PER_SPEED;
+    AngleSpeed {
+        angle: degrees.round().clamp(0.0, AngleSpeed::MAX_ANGLE as f32) as u8,
+        speed: speed
+            .round()
+            .clamp(AngleSpeed::MIN_SPEED as f32, AngleSpeed::MAX_SPEED as f32) as u8,
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum TypedField {
+    #[default]
+    Angle,
+    Speed,
+}
+
+/// What the current player has typed so far when aiming with numbers
+#[derive(Resource, Default)]
+pub(crate) struct TypedAim {
+    field: TypedField,
+    angle: String,
+    speed: String,
+    error: Option<String>,
+}
+
+impl TypedAim {
+    fn value_mut(&mut self) -> &mut String {
+        match self.field {
+            TypedField::Angle => &mut self.angle,
+            TypedField::Speed => &mut self.speed,
+        }
+    }
+
+    /// the field as it should be shown, with a cursor if it is being typed in
+    pub(crate) fn field_text(&self, field: TypedField) -> String {
+        let value = match field {
+            TypedField::Angle => &self.angle,
+            TypedField::Speed => &self.speed,
+        };
+        let cursor = if self.field == field { "_" } else { "" };
+        match (field, &self.error) {
+            (TypedField::Speed, Some(e)) => format!("{value}{cursor}\n{e}"),
+            _ => format!("{value}{cursor}"),
+        }
+    }
+}
+
+pub(crate) struct TypedAimPlugin;
+impl Plugin for TypedAimPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TypedAim>()
+            .add_systems(OnEnter(Action::Enter), reset_typed_aim)
+            .add_systems(
+                Update,
+                type_to_aim
+                    .run_if(aim_mode_is(AimMode::Typed))
+                    .in_set(InGameplaySet::Gorillas),
+            );
+    }
+}
+
+fn reset_typed_aim(mut typed_aim: ResMut<TypedAim>) {
+    *typed_aim = TypedAim::default();
+}
+
+fn type_to_aim(
+    player: Res<State<Player>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    mut typed_aim: ResMut<TypedAim>,
+    mut gorilla_query: Query<(&Gorilla, &mut AngleSpeed)>,
+    mut throw_events: EventWriter<ThrowBananaEvent>,
+) {
+    for c in characters.read() {
+        for digit in c.char.chars().filter(char::is_ascii_digit) {
+            let value = typed_aim.value_mut();
+            if value.len() < MAX_TYPED_DIGITS {
+                value.push(digit);
+            }
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Backspace) {
+        typed_aim.value_mut().pop();
+    }
+    if keyboard_input.just_pressed(KeyCode::Tab) {
+        typed_aim.field = match typed_aim.field {
+            TypedField::Angle => TypedField::Speed,
+            TypedField::Speed => TypedField::Angle,
+        };
+    }
+    if !keyboard_input.any_just_pressed([KeyCode::Enter, KeyCode::NumpadEnter]) {
+        return;
+    }
+
+    // same ranges as the arrow keys allow
+    let angle = parse_in_range(&typed_aim.angle, "Angle", 0, AngleSpeed::MAX_ANGLE);
+    let speed = parse_in_range(
+        &typed_aim.speed,
+        "Velocity",
+        AngleSpeed::MIN_SPEED,
+        AngleSpeed::MAX_SPEED,
+    );
+    match (typed_aim.field, angle, speed) {
+        (TypedField::Angle, Ok(_), _) => {
+            typed_aim.error = None;
+            typed_aim.field = TypedField::Speed;
+        }
+        (_, Err(e), _) => {
+            typed_aim.error = Some(e);
+            typed_aim.field = TypedField::Angle;
+        }
+        (TypedField::Speed, Ok(_), Err(e)) => {
+            typed_aim.error = Some(e);
+        }
+        (TypedField::Speed, Ok(angle), Ok(speed)) => {
+            typed_aim.error = None;
+            for (g, mut a) in gorilla_query.iter_mut() {
+                if &g.player == player.get() {
+                    *a = AngleSpeed { angle, speed };
+                    info!("typed throw for {:?} {}@{}", g.player, speed, angle);
+                    throw_events.send(ThrowBananaEvent);
+                }
+            }
+        }
+    }
+}
+
+fn parse_in_range(value: &str, name: &str, min: u8, max: u8) -> Result<u8, String> {
+    value
+        .parse::<u8>()
+        .ok()
+        .filter(|v| (min..=max).contains(v))
+        .ok_or(format!("{name} must be {min} to {max}"))
+}
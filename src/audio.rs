@@ -1,34 +1,476 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+use std::{env, io};
+
+use serde::{Deserialize, Serialize};
+
+use crate::controls::{any_action_just_pressed, config_path, GameAction};
+use crate::game::{Action, ExplodeBrick, Explosion};
 use crate::prelude::*;
-use bevy_kira_audio::{Audio, AudioControl, AudioPlugin, AudioSource};
+use crate::rules::MatchRules;
+use crate::turn_timer::TurnTimerWarningEvent;
+use bevy_kira_audio::{AudioApp, AudioChannel, AudioControl, AudioPlugin, AudioSource};
 
 pub(crate) mod prelude {
     pub(crate) use super::GorillasAudioPlugin;
 }
 
+const SOUNDS_FILE: &str = "sounds.ron";
+// kept with the player's own config, the assets are only ever read
+const SETTINGS_DIR: &str = "gorillas";
+const SETTINGS_FILE: &str = "audio.ron";
+const VOLUME_STEP: f64 = 0.1;
+
+/// Everything the game makes a noise for, what each one sounds like is up to the sound
+/// bank
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) enum Sound {
+    Explosion,
+    Throw,
+    /// loops while watching a banana fly
+    Whoosh,
+    Crumble,
+    GorillaHit,
+    Victory,
+    WindChange,
+    AimTick,
+    TurnTimerWarning,
+    /// loops on the music channel for the whole match
+    Music,
+}
+
+/// A file to play for a sound and how to play it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+struct SoundDef {
+    /// relative to the assets folder
+    file: String,
+    volume: f64,
+    /// 2.0 plays it twice as fast and an octave higher
+    rate: f64,
+}
+
+impl Default for SoundDef {
+    fn default() -> Self {
+        SoundDef {
+            file: String::new(),
+            volume: 1.0,
+            rate: 1.0,
+        }
+    }
+}
+
+/// Which file plays for each sound, anything left out stays quiet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct SoundBankConfig {
+    sounds: HashMap<Sound, SoundDef>,
+}
+
+impl SoundBankConfig {
+    fn load() -> Self {
+        let path = config_path(SOUNDS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(contents) => match ron::from_str(&contents) {
+                Ok(config) => config,
+                Err(e) => {
+                    error!(
+                        "unable to parse {}, using default sounds: {e}",
+                        path.display()
+                    );
+                    SoundBankConfig::default()
+                }
+            },
+            Err(e) => {
+                warn!(
+                    "unable to read {}, using default sounds: {e}",
+                    path.display()
+                );
+                SoundBankConfig::default()
+            }
+        }
+    }
+}
+
+impl Default for SoundBankConfig {
+    fn default() -> Self {
+        // the explosion is the only sound there is, played faster or slower for the rest
+        let explosion = |volume, rate| SoundDef {
+            file: "sounds/explosion.mp3".to_string(),
+            volume,
+            rate,
+        };
+        SoundBankConfig {
+            sounds: HashMap::from([
+                (Sound::Explosion, explosion(1.0, 1.0)),
+                (Sound::Throw, explosion(0.25, 3.0)),
+                (Sound::Whoosh, explosion(0.15, 0.4)),
+                (Sound::Crumble, explosion(0.4, 1.8)),
+                (Sound::GorillaHit, explosion(1.0, 0.7)),
+                (Sound::Victory, explosion(0.8, 0.5)),
+                (Sound::WindChange, explosion(0.2, 0.6)),
+                (Sound::AimTick, explosion(0.1, 4.0)),
+                (Sound::TurnTimerWarning, explosion(0.3, 2.5)),
+                // a slow rumble until there is some real music
+                (Sound::Music, explosion(0.3, 0.25)),
+            ]),
+        }
+    }
+}
+
 #[derive(Resource)]
-struct ExplosionSound(Handle<AudioSource>);
+struct SoundBank {
+    sounds: HashMap<Sound, (Handle<AudioSource>, SoundDef)>,
+}
+
+impl SoundBank {
+    fn play(&self, channel: &impl AudioControl, sound: Sound, looped: bool) {
+        let Some((handle, def)) = self.sounds.get(&sound) else {
+            return;
+        };
+        let mut command = channel.play(handle.clone());
+        command.with_volume(def.volume).with_playback_rate(def.rate);
+        if looped {
+            command.looped();
+        }
+    }
+}
+
+/// Ask for a sound to be played
+#[derive(Event, Debug)]
+pub(crate) struct PlaySoundEvent(pub(crate) Sound);
+
+/// How loud everything is, kept between matches
+#[derive(Resource, Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+struct AudioSettings {
+    /// every channel is turned down by this as well
+    master: f64,
+    effects: f64,
+    music: f64,
+    muted: bool,
+}
+
+impl Default for AudioSettings {
+    fn default() -> Self {
+        AudioSettings {
+            master: 1.0,
+            effects: 1.0,
+            music: 0.5,
+            muted: false,
+        }
+    }
+}
+
+impl AudioSettings {
+    fn load() -> Self {
+        let Some(path) = settings_path() else {
+            return AudioSettings::default();
+        };
+        match fs::read_to_string(&path) {
+            Ok(contents) => match ron::from_str(&contents) {
+                Ok(settings) => settings,
+                Err(e) => {
+                    error!(
+                        "unable to parse {}, using default volume: {e}",
+                        path.display()
+                    );
+                    AudioSettings::default()
+                }
+            },
+            // nothing has been saved yet
+            Err(_) => AudioSettings::default(),
+        }
+    }
+
+    fn save(&self) {
+        let Some(path) = settings_path() else {
+            warn!("nowhere to save the volume, no config directory");
+            return;
+        };
+        let saved = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                path.parent()
+                    .map_or(Ok(()), fs::create_dir_all)
+                    .and_then(|_| fs::write(&path, contents))
+                    .map_err(|e: io::Error| e.to_string())
+            });
+        if let Err(e) = saved {
+            warn!("unable to save {}: {e}", path.display());
+        }
+    }
+
+    fn channel_volume(&self, volume: f64) -> f64 {
+        if self.muted {
+            0.0
+        } else {
+            self.master * volume
+        }
+    }
+}
+
+/// `gorillas/audio.ron` in the platform's config directory, if there is one
+fn settings_path() -> Option<PathBuf> {
+    let config_dir = env::var_os("XDG_CONFIG_HOME")
+        .or_else(|| env::var_os("APPDATA"))
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| env::var_os("HOME").map(|home| PathBuf::from(home).join(".config")))?;
+    Some(config_dir.join(SETTINGS_DIR).join(SETTINGS_FILE))
+}
+
+/// One off sound effects
+#[derive(Resource)]
+struct EffectsChannel;
+
+/// Sound effects that keep going until they are stopped, as loud as the other effects
+#[derive(Resource)]
+struct LoopChannel;
+
+#[derive(Resource)]
+struct MusicChannel;
+
+/// Without an audio device sounds are still asked for, they just go nowhere
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+enum AudioBackend {
+    Kira,
+    Null,
+}
+
+fn kira_backend(backend: Res<AudioBackend>) -> bool {
+    *backend == AudioBackend::Kira
+}
 
 pub(crate) struct GorillasAudioPlugin;
 impl Plugin for GorillasAudioPlugin {
     fn build(&self, app: &mut App) {
-        app.add_plugins(AudioPlugin)
-            .add_systems(Startup, setup)
-            .add_systems(Update, play_collision_sound);
+        let backend = match app.world.get_resource::<MatchRules>() {
+            Some(rules) if !rules.audio => AudioBackend::Null,
+            _ => AudioBackend::Kira,
+        };
+        info!("audio backend {:?}", backend);
+        if backend == AudioBackend::Kira {
+            app.add_plugins(AudioPlugin)
+                .add_audio_channel::<EffectsChannel>()
+                .add_audio_channel::<LoopChannel>()
+                .add_audio_channel::<MusicChannel>();
+        }
+        app.insert_resource(backend)
+            .insert_resource(AudioSettings::load())
+            .add_event::<PlaySoundEvent>()
+            // audio files can only be loaded with the audio plugin
+            .add_systems(Startup, (setup, start_music).chain().run_if(kira_backend))
+            .add_systems(OnEnter(Action::Throwing), throw_sound)
+            .add_systems(OnEnter(Action::Winner), victory_sound)
+            .add_systems(OnEnter(Action::Watching), start_whoosh.run_if(kira_backend))
+            .add_systems(OnExit(Action::Watching), stop_whoosh.run_if(kira_backend))
+            .add_systems(
+                Update,
+                (
+                    (
+                        collision_sounds,
+                        crumble_sounds,
+                        turn_timer_warning_sounds,
+                        toggle_mute.run_if(any_action_just_pressed(GameAction::Mute)),
+                        volume_up.run_if(any_action_just_pressed(GameAction::VolumeUp)),
+                        volume_down.run_if(any_action_just_pressed(GameAction::VolumeDown)),
+                    ),
+                    (
+                        play_sounds.run_if(kira_backend),
+                        ignore_sounds.run_if(not(kira_backend)),
+                        apply_volume
+                            .run_if(kira_backend)
+                            .run_if(resource_changed::<AudioSettings>),
+                        save_settings.run_if(
+                            resource_changed::<AudioSettings>
+                                .and_then(not(resource_added::<AudioSettings>)),
+                        ),
+                    ),
+                )
+                    .chain(),
+            );
     }
 }
 
 fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    let explosion_sounds = asset_server.load("sounds/explosion.mp3");
-    commands.insert_resource(ExplosionSound(explosion_sounds));
+    let config = SoundBankConfig::load();
+    let sounds = config
+        .sounds
+        .into_iter()
+        .map(|(sound, def)| (sound, (asset_server.load(def.file.clone()), def)))
+        .collect();
+    commands.insert_resource(SoundBank { sounds });
+}
+
+fn play_sounds(
+    mut events: EventReader<PlaySoundEvent>,
+    channel: Res<AudioChannel<EffectsChannel>>,
+    bank: Res<SoundBank>,
+) {
+    for PlaySoundEvent(sound) in events.read() {
+        debug!("play sound {:?}", sound);
+        bank.play(&*channel, *sound, false);
+    }
 }
 
-fn play_collision_sound(
+fn ignore_sounds(mut events: EventReader<PlaySoundEvent>) {
+    for PlaySoundEvent(sound) in events.read() {
+        debug!("no audio, not playing {:?}", sound);
+    }
+}
+
+fn start_music(channel: Res<AudioChannel<MusicChannel>>, bank: Res<SoundBank>) {
+    bank.play(&*channel, Sound::Music, true);
+}
+
+fn apply_volume(
+    settings: Res<AudioSettings>,
+    effects: Res<AudioChannel<EffectsChannel>>,
+    loops: Res<AudioChannel<LoopChannel>>,
+    music: Res<AudioChannel<MusicChannel>>,
+) {
+    effects.set_volume(settings.channel_volume(settings.effects));
+    loops.set_volume(settings.channel_volume(settings.effects));
+    music.set_volume(settings.channel_volume(settings.music));
+}
+
+fn save_settings(settings: Res<AudioSettings>) {
+    settings.save();
+}
+
+fn toggle_mute(mut settings: ResMut<AudioSettings>) {
+    settings.muted = !settings.muted;
+    info!("muted {}", settings.muted);
+}
+
+fn volume_up(mut settings: ResMut<AudioSettings>) {
+    settings.muted = false;
+    settings.master = (settings.master + VOLUME_STEP).min(1.0);
+    info!("volume {:.1}", settings.master);
+}
+
+fn volume_down(mut settings: ResMut<AudioSettings>) {
+    settings.master = (settings.master - VOLUME_STEP).max(0.0);
+    info!("volume {:.1}", settings.master);
+}
+
+fn collision_sounds(
     mut collision_events: EventReader<BananaCollisionEvent>,
-    audio: Res<Audio>,
-    sound: Res<ExplosionSound>,
+    mut gorilla_events: EventReader<GorillaCollisionEvent>,
+    mut sounds: EventWriter<PlaySoundEvent>,
+    explosion_query: Query<(), With<Explosion>>,
+    mut played: Local<HashSet<(Entity, Player)>>,
 ) {
     for evt in collision_events.read() {
         info!("play sound for {:?}", evt);
-        audio.play(sound.0.clone());
+        sounds.send(PlaySoundEvent(Sound::Explosion));
+    }
+    // a blast keeps touching a gorilla for as long as it lasts, only the first touch
+    // makes a sound
+    played.retain(|(explosion, _)| explosion_query.contains(*explosion));
+    for evt in gorilla_events.read() {
+        if played.insert((evt.explosion, evt.player.clone())) {
+            info!("play sound for {:?} being hit", evt.player);
+            sounds.send(PlaySoundEvent(Sound::GorillaHit));
+        }
+    }
+}
+
+/// once for every blast, however many bricks it knocks out
+fn crumble_sounds(query: Query<(), Added<ExplodeBrick>>, mut sounds: EventWriter<PlaySoundEvent>) {
+    if !query.is_empty() {
+        sounds.send(PlaySoundEvent(Sound::Crumble));
+    }
+}
+
+fn turn_timer_warning_sounds(
+    mut warning_events: EventReader<TurnTimerWarningEvent>,
+    mut sounds: EventWriter<PlaySoundEvent>,
+) {
+    for _ in warning_events.read() {
+        info!("play turn timer warning");
+        sounds.send(PlaySoundEvent(Sound::TurnTimerWarning));
+    }
+}
+
+fn throw_sound(mut sounds: EventWriter<PlaySoundEvent>) {
+    sounds.send(PlaySoundEvent(Sound::Throw));
+}
+
+fn victory_sound(mut sounds: EventWriter<PlaySoundEvent>) {
+    sounds.send(PlaySoundEvent(Sound::Victory));
+}
+
+fn start_whoosh(channel: Res<AudioChannel<LoopChannel>>, bank: Res<SoundBank>) {
+    bank.play(&*channel, Sound::Whoosh, true);
+}
+
+fn stop_whoosh(channel: Res<AudioChannel<LoopChannel>>) {
+    channel.stop();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::controls::PlayerActions;
+
+    #[test]
+    fn shipped_sound_bank_has_every_sound() {
+        let config: SoundBankConfig =
+            ron::from_str(include_str!("../assets/config/sounds.ron")).unwrap();
+        for sound in [
+            Sound::Explosion,
+            Sound::Throw,
+            Sound::Whoosh,
+            Sound::Crumble,
+            Sound::GorillaHit,
+            Sound::Victory,
+            Sound::WindChange,
+            Sound::AimTick,
+            Sound::TurnTimerWarning,
+            Sound::Music,
+        ] {
+            assert!(config.sounds.contains_key(&sound), "{sound:?} is quiet");
+            assert!(
+                SoundBankConfig::default().sounds.contains_key(&sound),
+                "{sound:?} is quiet by default"
+            );
+        }
+    }
+
+    #[test]
+    fn no_audio_runs_without_a_device() {
+        let mut app = App::new();
+        app.insert_resource(MatchRules {
+            audio: false,
+            ..default()
+        })
+        .init_state::<Action>()
+        .init_resource::<PlayerActions>()
+        .add_event::<BananaCollisionEvent>()
+        .add_event::<GorillaCollisionEvent>()
+        .add_event::<TurnTimerWarningEvent>()
+        .add_plugins(GorillasAudioPlugin);
+        app.update();
+        assert_eq!(*app.world.resource::<AudioBackend>(), AudioBackend::Null);
+        assert!(!app.world.contains_resource::<SoundBank>());
+
+        // sounds are still asked for, they just go nowhere
+        app.world.send_event(BananaCollisionEvent {
+            banana_entity: Entity::PLACEHOLDER,
+        });
+        app.world
+            .resource_mut::<NextState<Action>>()
+            .set(Action::Throwing);
+        app.update();
+        let mut asked: Vec<_> = app
+            .world
+            .resource_mut::<Events<PlaySoundEvent>>()
+            .drain()
+            .map(|PlaySoundEvent(sound)| sound)
+            .collect();
+        asked.sort_by_key(|sound| format!("{sound:?}"));
+        assert_eq!(asked, [Sound::Explosion, Sound::Throw]);
     }
 }
@@ -1,74 +1,137 @@
-use std::f32::consts::PI;
-
-use bevy::input::common_conditions::input_just_pressed;
-
-use crate::game::{Action, AngleSpeed};
+use crate::controls::{action_just_pressed, aim_mode_is, AimMode, GameAction};
+use crate::defense::TurnAction;
+use crate::game::{Action, AngleSpeed, InGameplaySet};
+use crate::level::Level;
+use crate::movement::no_one_airborne;
+use crate::pickups::PowerUps;
+use crate::planning::{lock_in, Plans};
 use crate::prelude::*;
+use crate::projectile::{Ammo, ProjectileKind, Weapon};
+
+// the banana sprite is 64x64 so scale it down to the banana size
+pub(crate) const BANANA_SCALE: Vec2 = Vec2::new(BANANA_WIDTH / 64.0, BANANA_HEIGHT / 64.0);
 
 #[derive(Component)]
 pub(crate) struct Banana {
-    thrown_by: Player,
+    pub(crate) thrown_by: Player,
+    pub(crate) kind: ProjectileKind,
+    pub(crate) bounces_left: u8,
+    pub(crate) pierces_left: u8,
 }
 
 #[derive(Resource, Event)]
 pub(crate) struct BananaGoneEvent;
 
+/// Everything thrown this turn, including the pieces of anything that split up, the
+/// turn is only over once all of it has blown up or left the arena
+#[derive(Resource, Default, Debug)]
+pub(crate) struct TurnProjectiles {
+    thrown: usize,
+    resolved: usize,
+}
+
+impl TurnProjectiles {
+    pub(crate) fn all_resolved(&self) -> bool {
+        self.thrown > 0 && self.resolved >= self.thrown
+    }
+}
+
+/// ask for the current player to throw with their current angle and speed
+#[derive(Event)]
+pub(crate) struct ThrowBananaEvent;
+
 pub(crate) struct BananaPlugin;
 impl Plugin for BananaPlugin {
     fn build(&self, app: &mut App) {
-        app.add_event::<BananaGoneEvent>().add_systems(
-            Update,
-            (
-                throw_banana
-                    .run_if(in_state(Action::Enter))
-                    .run_if(input_just_pressed(KeyCode::Space)),
-                transition_to_watching_banana.run_if(in_state(Action::Throwing)),
-                check_banana_off_screen.run_if(in_state(Action::Watching)),
-            ),
-        );
+        app.init_resource::<TurnProjectiles>()
+            .add_event::<BananaGoneEvent>()
+            .add_event::<ThrowBananaEvent>()
+            .add_systems(OnEnter(Action::Enter), reset_turn_projectiles)
+            .add_systems(OnEnter(Action::Planning), reset_turn_projectiles)
+            .add_systems(
+                Update,
+                // after bananas blow up and before deciding if the turn is over
+                track_turn_projectiles
+                    .after(InGameplaySet::Collisions)
+                    .before(InGameplaySet::TurnChanges),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        // typed aims throw once both fields are entered
+                        request_throw
+                            .run_if(action_just_pressed(GameAction::Fire))
+                            .run_if(aim_mode_is(AimMode::Arrows)),
+                        lock_in
+                            .run_if(on_event::<ThrowBananaEvent>())
+                            .run_if(in_state(Action::Planning)),
+                        throw_banana.run_if(on_event::<ThrowBananaEvent>()),
+                    )
+                        .chain()
+                        .run_if(in_state(TurnAction::Throw))
+                        .run_if(no_one_airborne)
+                        .in_set(InGameplaySet::Gorillas),
+                    transition_to_watching_banana.run_if(in_state(Action::Throwing)),
+                    check_banana_off_screen.run_if(in_state(Action::Watching)),
+                ),
+            );
     }
 }
 
+fn request_throw(mut events: EventWriter<ThrowBananaEvent>) {
+    events.send(ThrowBananaEvent);
+}
+
+/// the current player throws, or when planning everyone throws at once as soon as
+/// they have all locked in
 fn throw_banana(
     mut next_action: ResMut<NextState<crate::game::Action>>,
+    action: Res<State<Action>>,
     player: Res<State<Player>>,
+    plans: Res<Plans>,
     asset_server: Res<AssetServer>,
-    gorilla_query: Query<(&Gorilla, &Transform, &AngleSpeed)>,
+    mut gorilla_query: Query<(
+        &Gorilla,
+        &Transform,
+        &AngleSpeed,
+        &mut Weapon,
+        &mut Ammo,
+        &mut PowerUps,
+    )>,
     mut commands: Commands,
 ) {
-    for (g, t, a) in gorilla_query.iter() {
-        if &g.player == player.get() {
-            let angle = a.angle;
-            let speed = a.speed;
-            // if left alone compass looks like this, but we want to make 90 straight up
-            // and for 100 to be behind the head
-            //        0
-            // 270 <- * -> 90
-            //       180
-            //
-            // so we are just going to do (90 - *degrees*)
-            // to make it go
-            //       90
-            // 180 <- * -> 0
-            //       270
-            let radians = (90f32 - (angle as f32)) * PI / 180.0;
-            let mut v = Vec2::new(
-                radians.sin() * (speed as f32),
-                radians.cos() * (speed as f32),
-            );
-
-            // scale, then reverse for player 2
-            v *= PIXEL_STEP_SIZE / 1.5;
-            if player.get() == &Player::Two {
-                v.x *= -1.0
+    let throwers = if action.get() == &Action::Planning {
+        let ready = plans.ready(
+            gorilla_query
+                .iter()
+                .filter(|(_, _, _, _, ammo, _)| !ammo.is_empty())
+                .map(|(g, _, _, _, _, _)| &g.player),
+        );
+        if !ready {
+            return;
+        }
+        plans.locked_in().to_vec()
+    } else {
+        vec![player.get().clone()]
+    };
+    for (g, t, a, mut w, mut ammo, mut power_ups) in gorilla_query.iter_mut() {
+        if throwers.contains(&g.player) {
+            if !ammo.take(w.0) {
+                warn!("{:?} has no {} left to throw", g.player, w.def().name);
+                continue;
             }
-            spawn_banana(
+            let v = launch_velocity(&g.player, a.angle, a.speed);
+            let banana = spawn_banana(
                 &asset_server,
-                player.clone(),
+                g.player.clone(),
                 &mut commands,
                 t.translation.truncate(),
                 v,
+                w.0,
             );
+            power_ups.use_on(&mut commands.entity(banana));
+            w.keep_in_stock(&ammo);
             next_action.set(Action::Throwing);
         }
     }
@@ -79,58 +142,144 @@ fn transition_to_watching_banana(
     gorilla_query: Query<(&Transform, &Gorilla)>,
     banana_query: Query<(&Transform, &Banana)>,
 ) {
-    for (bt, b) in banana_query.iter() {
-        // if any banana is far away from the gorilla set us into watching state
+    // once every banana is far away from the gorilla set us into watching state
+    let all_away = banana_query.iter().all(|(bt, b)| {
         let mut min_distance = f32::MAX;
         for (gt, g) in gorilla_query.iter() {
             if g.player == b.thrown_by {
                 min_distance = min_distance.min(gt.translation.distance(bt.translation))
             }
         }
-        if min_distance > 50.0 {
-            next_action.set(Action::Watching);
-        }
+        min_distance > BANANA_WATCH_DISTANCE
+    });
+    if all_away && !banana_query.is_empty() {
+        next_action.set(Action::Watching);
     }
 }
 
-fn spawn_banana(
+fn reset_turn_projectiles(mut projectiles: ResMut<TurnProjectiles>) {
+    *projectiles = TurnProjectiles::default();
+}
+
+/// bananas are resolved when they are despawned, whether they blew up, left the
+/// arena or split into pieces that are then tracked themselves
+fn track_turn_projectiles(
+    mut projectiles: ResMut<TurnProjectiles>,
+    thrown_query: Query<(), Added<Banana>>,
+    mut resolved: RemovedComponents<Banana>,
+) {
+    projectiles.thrown += thrown_query.iter().count();
+    projectiles.resolved += resolved.read().count();
+}
+
+pub(crate) fn spawn_banana(
     asset_server: &Res<AssetServer>,
     player: Player,
     commands: &mut Commands,
     g_pos: Vec2,
     initial_velocity: Vec2,
-) {
-    let banana_rotation: Quat = Quat::from_rotation_z(PI * -TIME_STEP);
-    commands.spawn((
-        Banana { thrown_by: player },
-        SpriteBundle {
-            transform: Transform {
-                translation: g_pos.extend(BANANA_Z_INDEX),
-                scale: Vec2::new(BANANA_WIDTH / 64.0, BANANA_HEIGHT / 64.0).extend(1.0),
+    kind: ProjectileKind,
+) -> Entity {
+    let def = kind.def();
+    let banana_rotation: Quat = Quat::from_rotation_z(def.spin);
+    commands
+        .spawn((
+            Banana {
+                thrown_by: player,
+                kind,
+                bounces_left: def.bounces,
+                pierces_left: def.pierces,
+            },
+            SpriteBundle {
+                transform: Transform {
+                    translation: g_pos.extend(BANANA_Z_INDEX),
+                    scale: (BANANA_SCALE * def.size).extend(1.0),
+                    ..default()
+                },
+                sprite: Sprite {
+                    color: def.tint,
+                    ..default()
+                },
+                texture: asset_server.load("sprites/banana_64x64.png"),
                 ..default()
             },
-            texture: asset_server.load("sprites/banana_64x64.png"),
-            ..default()
-        },
-        MovementState::new(g_pos),
-        Velocity(initial_velocity),
-        Rotation(banana_rotation),
-    ));
+            MovementState::new(g_pos),
+            Velocity(initial_velocity),
+            Rotation(banana_rotation),
+            WindFactor(def.wind_factor),
+        ))
+        .id()
 }
 
 fn check_banana_off_screen(
     mut commands: Commands,
+    level: Res<Level>,
     mut events: EventWriter<BananaGoneEvent>,
     banana_query: Query<(Entity, &Transform), With<Banana>>,
 ) {
     for (banana_entity, banana_transform) in banana_query.iter() {
-        if banana_transform.translation.x <= -SCREEN_WIDTH / 2.0
-            || banana_transform.translation.x >= SCREEN_WIDTH / 2.0
-            || banana_transform.translation.y <= -SCREEN_HEIGHT / 2.0
-        {
+        if is_off_screen(banana_transform.translation.truncate(), &level) {
             info!("sending banana gone event");
             commands.entity(banana_entity).despawn_recursive();
             events.send(BananaGoneEvent);
         }
     }
 }
+
+/// bananas can still come back down from above the arena
+pub(crate) fn is_off_screen(position: Vec2, level: &Level) -> bool {
+    position.x <= -level.width / 2.0
+        || position.x >= level.width / 2.0
+        || position.y <= -level.height / 2.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn banana(kind: ProjectileKind) -> Banana {
+        Banana {
+            thrown_by: Player::One,
+            kind,
+            bounces_left: 0,
+            pierces_left: 0,
+        }
+    }
+
+    #[test]
+    fn turn_stays_open_until_every_fragment_is_resolved() {
+        let mut app = App::new();
+        app.init_resource::<TurnProjectiles>()
+            .add_systems(Update, track_turn_projectiles);
+        app.update();
+        assert!(
+            !app.world.resource::<TurnProjectiles>().all_resolved(),
+            "nothing has been thrown yet"
+        );
+
+        let cluster = app.world.spawn(banana(ProjectileKind::Cluster)).id();
+        app.update();
+        assert!(!app.world.resource::<TurnProjectiles>().all_resolved());
+
+        // it splits, going away in the same frame as its pieces turn up
+        app.world.despawn(cluster);
+        let fragments: Vec<_> = (0..CLUSTER_FRAGMENTS)
+            .map(|_| app.world.spawn(banana(ProjectileKind::Fragment)).id())
+            .collect();
+        app.update();
+        assert!(!app.world.resource::<TurnProjectiles>().all_resolved());
+
+        for (i, fragment) in fragments.iter().enumerate() {
+            app.world.despawn(*fragment);
+            app.update();
+            let last = i + 1 == fragments.len();
+            assert_eq!(
+                app.world.resource::<TurnProjectiles>().all_resolved(),
+                last,
+                "after {} of {} fragments",
+                i + 1,
+                fragments.len()
+            );
+        }
+    }
+}
@@ -0,0 +1,168 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use bevy::input::mouse::{MouseScrollUnit, MouseWheel};
+use bevy::render::camera::ScalingMode;
+use bevy::transform::TransformSystem;
+
+use crate::controls::{action_just_pressed, GameAction};
+use crate::game::{Action, InGameplaySet};
+use crate::level::Level;
+use crate::prelude::*;
+
+// how much of the way to the whole arena one notch of the mouse wheel zooms
+const OVERVIEW_WHEEL_STEP: f32 = 0.1;
+// scrolling by pixels, like a touchpad, is much finer than by lines
+const PIXELS_PER_LINE: f32 = 100.0;
+
+/// The camera following the action, anything parented to it stays put on the screen
+#[derive(Component)]
+pub(crate) struct GameCamera;
+
+/// How far out the player has zoomed while aiming,
+/// 0.0 is the thrower up close and 1.0 is the whole arena
+#[derive(Resource, Default)]
+struct Overview {
+    zoom: f32,
+}
+
+pub(crate) struct CameraPlugin;
+impl Plugin for CameraPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Overview>()
+            .add_systems(Startup, setup_camera)
+            .add_systems(OnEnter(Action::Enter), reset_overview)
+            .add_systems(
+                Update,
+                (
+                    toggle_overview.run_if(action_just_pressed(GameAction::Overview)),
+                    wheel_overview,
+                )
+                    .in_set(InGameplaySet::Gorillas),
+            )
+            .add_systems(
+                PostUpdate,
+                follow_action.before(TransformSystem::TransformPropagate),
+            );
+    }
+}
+
+/// the part of the arena shown up close, all of it unless it is bigger than the view
+pub(crate) fn view_size(level: &Level) -> Vec2 {
+    Vec2::new(level.width.min(VIEW_WIDTH), level.height.min(VIEW_HEIGHT))
+}
+
+/// zoom that fits the whole arena in the view
+fn whole_arena_zoom(level: &Level) -> f32 {
+    let view = view_size(level);
+    (level.width / view.x).max(level.height / view.y)
+}
+
+pub(crate) fn setup_camera(mut commands: Commands, level: Res<Level>) {
+    // the view always fits whatever the size or shape of the window,
+    // zooming scales the camera so that its children keep their size on screen
+    let view = view_size(&level);
+    let mut camera = Camera2dBundle::default();
+    camera.projection.scaling_mode = ScalingMode::AutoMin {
+        min_width: view.x,
+        min_height: view.y,
+    };
+    commands.spawn((GameCamera, camera));
+}
+
+fn reset_overview(mut overview: ResMut<Overview>) {
+    overview.zoom = 0.0;
+}
+
+fn toggle_overview(mut overview: ResMut<Overview>) {
+    overview.zoom = if overview.zoom < 0.5 { 1.0 } else { 0.0 };
+}
+
+fn wheel_overview(mut wheel_events: EventReader<MouseWheel>, mut overview: ResMut<Overview>) {
+    for event in wheel_events.read() {
+        let lines = match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / PIXELS_PER_LINE,
+        };
+        // scrolling up zooms in
+        overview.zoom = (overview.zoom - lines * OVERVIEW_WHEEL_STEP).clamp(0.0, 1.0);
+    }
+}
+
+fn follow_action(
+    time: Res<Time>,
+    level: Res<Level>,
+    action: Res<State<Action>>,
+    player: Res<State<Player>>,
+    overview: Res<Overview>,
+    gorilla_query: Query<(&Gorilla, &Transform), Without<GameCamera>>,
+    banana_query: Query<&Transform, (With<Banana>, Without<GameCamera>)>,
+    mut camera_query: Query<(&mut Transform, &OrthographicProjection), With<GameCamera>>,
+) {
+    let Ok((mut camera_transform, projection)) = camera_query.get_single_mut() else {
+        return;
+    };
+    let gorilla_position = |p: &Player| {
+        gorilla_query
+            .iter()
+            .find(|(g, _)| &g.player == p)
+            .map(|(_, t)| t.translation.truncate())
+    };
+    let thrower = gorilla_position(player.get()).unwrap_or_default();
+    let whole = whole_arena_zoom(&level);
+
+    let (center, zoom) = match action.get() {
+        Action::Enter | Action::Planning => (thrower, 1.0 + (whole - 1.0) * overview.zoom),
+        Action::Throwing | Action::Watching => {
+            let target = gorilla_position(match player.get() {
+                Player::One => &Player::Two,
+                Player::Two => &Player::One,
+            });
+            let bananas: Vec<Vec2> = banana_query
+                .iter()
+                .map(|t| t.translation.truncate())
+                .collect();
+            if bananas.is_empty() {
+                (thrower, 1.0)
+            } else {
+                fit(bananas.into_iter().chain(target), &level, whole)
+            }
+        }
+        Action::Winner | Action::Draw => (Vec2::ZERO, whole),
+    };
+
+    // the projection area is what the view covers without any zoom
+    let half_visible = projection.area.half_size() * zoom;
+    let center = Vec2::new(
+        clamp_to_arena(center.x, half_visible.x, level.width / 2.0),
+        clamp_to_arena(center.y, half_visible.y, level.height / 2.0),
+    );
+
+    let t = 1.0 - (-CAMERA_EASING * time.delta_seconds()).exp();
+    let eased = camera_transform.translation.truncate().lerp(center, t);
+    camera_transform.translation.x = eased.x;
+    camera_transform.translation.y = eased.y;
+    let eased_zoom = camera_transform.scale.x + (zoom - camera_transform.scale.x) * t;
+    // z is left alone so nothing falls outside of the near and far planes
+    camera_transform.scale = Vec3::new(eased_zoom, eased_zoom, 1.0);
+}
+
+/// center and zoom that keeps every point on screen with a margin around them
+fn fit(points: impl Iterator<Item = Vec2>, level: &Level, whole: f32) -> (Vec2, f32) {
+    let (min, max) = points.fold(
+        (Vec2::splat(f32::MAX), Vec2::splat(f32::MIN)),
+        |(min, max), p| (min.min(p), max.max(p)),
+    );
+    let size = max - min + CAMERA_MARGIN * 2.0;
+    let view = view_size(level);
+    let zoom = (size.x / view.x).max(size.y / view.y).clamp(1.0, whole);
+    ((min + max) / 2.0, zoom)
+}
+
+/// keep the camera from showing past the edges of the arena, centering it when it shows all of it
+fn clamp_to_arena(center: f32, half_visible: f32, half_arena: f32) -> f32 {
+    if half_visible >= half_arena {
+        0.0
+    } else {
+        center.clamp(half_visible - half_arena, half_arena - half_visible)
+    }
+}
@@ -1,4 +1,7 @@
-use crate::game::{Action, BuildingBrick, ExplodeBrick, Explosion, InGameplaySet};
+use crate::game::{Action, BrickDamage, BuildingBrick, ExplodeBrick, Explosion, InGameplaySet};
+use crate::level::BrickMaterial;
+use crate::obstacles::{Obstacle, ObstacleHitEvent};
+use crate::pickups::{BigBlast, Crate, PickupEvent, PickupKind};
 use crate::prelude::*;
 use bevy::math::bounding::{Aabb2d, IntersectsVolume};
 use rand::{thread_rng, Rng};
@@ -6,6 +9,7 @@ use rand::{thread_rng, Rng};
 #[derive(Resource, Event)]
 pub(crate) struct GorillaCollisionEvent {
     pub(crate) player: Player,
+    pub(crate) explosion: Entity,
 }
 
 #[derive(Resource, Event, Debug)]
@@ -16,6 +20,45 @@ pub(crate) struct BananaCollisionEvent {
 #[derive(Component)]
 pub(crate) struct Collider;
 
+/// What is hitting the colliders, blasts reach a little further into glass
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum HitBy {
+    Banana,
+    /// a banana that is going to bounce off, so it does not damage anything
+    Touch,
+    Blast,
+}
+
+/// Everything a banana or a blast touched
+#[derive(Default)]
+struct Hit {
+    any: bool,
+    gorilla: Option<Player>,
+    /// how many bricks were broken
+    broken: usize,
+    /// touched something that did not break
+    solid: bool,
+    /// the way back out of everything touched
+    surfaces: Vec<Surface>,
+    /// the way to bounce off something the banana touched rather than blowing up,
+    /// and how much of its speed it keeps
+    deflect: Option<(Vec2, f32)>,
+    obstacles: Vec<ObstacleHitEvent>,
+    /// crates picked up along the way
+    pickups: Vec<(Entity, PickupKind)>,
+}
+
+/// The side of a collider that something is touching
+#[derive(Debug, Clone, Copy)]
+struct Surface {
+    /// out through the side it is least far into
+    normal: Vec2,
+    /// how far in it is through that side
+    depth: f32,
+    /// from the center of the collider to what is touching it
+    away: Vec2,
+}
+
 pub(crate) struct CollisionPlugin;
 impl Plugin for CollisionPlugin {
     fn build(&self, app: &mut App) {
@@ -39,46 +82,127 @@ impl Plugin for CollisionPlugin {
 
 fn check_for_collisions_explosion_gorilla(
     mut commands: Commands,
-    explosion_query: Query<&Transform, With<Explosion>>,
-    collider_query: Query<
-        (Entity, &Transform, Option<&BuildingBrick>, Option<&Gorilla>),
+    explosion_query: Query<(Entity, &Explosion, &Transform)>,
+    mut collider_query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&mut BuildingBrick>,
+            Option<&mut Sprite>,
+            Option<&Gorilla>,
+            Option<&Obstacle>,
+            Option<&Crate>,
+        ),
         With<Collider>,
     >,
     mut collision_event: EventWriter<GorillaCollisionEvent>,
+    mut obstacle_events: EventWriter<ObstacleHitEvent>,
 ) {
     // look up if explosion has hit something
-    for explosion_transform in explosion_query.iter() {
+    for (explosion_entity, explosion, explosion_transform) in explosion_query.iter() {
         // setup the explosion to look like it is the width of the circle that we are creating
         let mut t = explosion_transform.clone();
         t.scale *= EXPLOSION_START_DIAMETER;
 
-        let (_, did_collide_with_gorilla) =
-            check_if_did_collide(&mut commands, &collider_query, &t);
+        let hit = check_if_did_collide(
+            &mut commands,
+            &mut collider_query,
+            &t,
+            explosion.banana,
+            HitBy::Blast,
+        );
 
-        if let Some(player) = did_collide_with_gorilla {
+        if let Some(player) = hit.gorilla {
             info!("Collision with {:?}", player);
-            collision_event.send(GorillaCollisionEvent { player });
+            collision_event.send(GorillaCollisionEvent {
+                player,
+                explosion: explosion_entity,
+            });
         }
+        obstacle_events.send_batch(hit.obstacles);
     }
 }
 
 fn check_for_collisions_banana(
     mut commands: Commands,
-    banana_query: Query<(Entity, &Transform), With<Banana>>,
-    collider_query: Query<
-        (Entity, &Transform, Option<&BuildingBrick>, Option<&Gorilla>),
+    mut banana_query: Query<(Entity, &mut Banana, &Transform, &mut Velocity)>,
+    mut collider_query: Query<
+        (
+            Entity,
+            &Transform,
+            Option<&mut BuildingBrick>,
+            Option<&mut Sprite>,
+            Option<&Gorilla>,
+            Option<&Obstacle>,
+            Option<&Crate>,
+        ),
         With<Collider>,
     >,
     mut collision_event: EventWriter<BananaCollisionEvent>,
+    mut obstacle_events: EventWriter<ObstacleHitEvent>,
+    mut pickup_events: EventWriter<PickupEvent>,
 ) {
     // look up if our banana has hit something
-    for (banana_entity, banana_transform) in banana_query.iter() {
-        let (did_collide, _) =
-            check_if_did_collide(&mut commands, &collider_query, banana_transform);
-        if did_collide {
+    for (banana_entity, mut banana, banana_transform, mut velocity) in banana_query.iter_mut() {
+        let hit_by = if banana.bounces_left > 0 {
+            HitBy::Touch
+        } else {
+            HitBy::Banana
+        };
+        let hit = check_if_did_collide(
+            &mut commands,
+            &mut collider_query,
+            banana_transform,
+            banana_entity,
+            hit_by,
+        );
+        if hit.any && hit_by == HitBy::Touch && hit.gorilla.is_none() {
+            // rubber bananas bounce off everything but gorillas
+            if let Some(normal) = bounce_normal(&hit.surfaces, velocity.0) {
+                bounce(&mut velocity, normal, RUBBER_BOUNCE);
+                banana.bounces_left -= 1;
+            } else if hit.surfaces.iter().any(|s| velocity.dot(s.away) < 0.0) {
+                // wedged in with no side to bounce off, better to blow up than sink
+                info!("rubber banana stuck, blowing up");
+                collision_event.send(BananaCollisionEvent { banana_entity });
+            }
+        } else if hit.any && !hit.solid && hit.broken <= usize::from(banana.pierces_left) {
+            info!("banana drilled through {} bricks", hit.broken);
+            banana.pierces_left -= hit.broken as u8;
+        } else if hit.any {
             info!("banana collided with something");
             collision_event.send(BananaCollisionEvent { banana_entity });
+        } else if let Some((normal, bounciness)) = hit.deflect {
+            bounce(&mut velocity, normal, bounciness);
         }
+        obstacle_events.send_batch(hit.obstacles);
+        pickup_events.send_batch(hit.pickups.into_iter().map(|(crate_entity, pickup)| {
+            PickupEvent {
+                crate_entity,
+                pickup,
+                player: banana.thrown_by.clone(),
+            }
+        }));
+    }
+}
+
+/// the side a banana bounces off, the one it is least far into of those it is heading
+/// into. `None` when it is not heading into any, so it is on the way out
+fn bounce_normal(surfaces: &[Surface], velocity: Vec2) -> Option<Vec2> {
+    surfaces
+        .iter()
+        .filter(|s| velocity.dot(s.normal) < 0.0)
+        .min_by(|a, b| a.depth.total_cmp(&b.depth))
+        .map(|s| s.normal)
+}
+
+/// bounces off a surface facing `normal`, keeping `bounciness` of the speed into it
+fn bounce(velocity: &mut Velocity, normal: Vec2, bounciness: f32) {
+    // only bounce while heading into it, so it does not bounce again on the way out
+    let into = velocity.dot(normal);
+    if into < 0.0 {
+        info!("banana bounced off something");
+        velocity.0 -= normal * into * (1.0 + bounciness);
     }
 }
 
@@ -103,59 +227,144 @@ fn decr_and_despawn_brick(
 
 fn check_if_did_collide(
     commands: &mut Commands,
-    collider_query: &Query<
-        (Entity, &Transform, Option<&BuildingBrick>, Option<&Gorilla>),
+    collider_query: &mut Query<
+        (
+            Entity,
+            &Transform,
+            Option<&mut BuildingBrick>,
+            Option<&mut Sprite>,
+            Option<&Gorilla>,
+            Option<&Obstacle>,
+            Option<&Crate>,
+        ),
         With<Collider>,
     >,
     moving_transform: &Transform,
-) -> (bool, Option<Player>) {
-    let mut did_collide = false;
-    let mut did_collide_with_gorilla = None;
+    banana: Entity,
+    hit_by: HitBy,
+) -> Hit {
+    let mut hit = Hit::default();
 
-    for (e, transform, maybe_building, maybe_gorilla) in collider_query.iter() {
+    for (e, transform, maybe_building, maybe_sprite, maybe_gorilla, maybe_obstacle, maybe_crate) in
+        collider_query.iter_mut()
+    {
+        let material = maybe_building.as_ref().map(|b| b.material);
+        let reach = match (hit_by, material) {
+            (HitBy::Blast, Some(BrickMaterial::Glass)) => GLASS_SHATTER_DISTANCE,
+            _ => 0.0,
+        };
         let collision = Aabb2d::new(
             moving_transform.translation.truncate(),
-            moving_transform.scale.truncate() / 2.0,
+            moving_transform.scale.truncate() / 2.0 + reach,
         )
         .intersects(&Aabb2d::new(
             transform.translation.truncate(),
             transform.scale.truncate() / 2.0,
         ));
         if collision {
-            did_collide = true;
+            if let Some(c) = maybe_crate {
+                // bananas pick crates up and blasts go straight through them
+                if hit_by != HitBy::Blast {
+                    commands.entity(e).remove::<Collider>();
+                    hit.pickups.push((e, c.pickup));
+                }
+                continue;
+            }
+            let center = transform.translation.truncate();
+            let away = moving_transform.translation.truncate() - center;
+            let overlap =
+                (moving_transform.scale.truncate() + transform.scale.truncate()) / 2.0 - away.abs();
+            hit.surfaces.push(if overlap.x < overlap.y {
+                Surface {
+                    normal: Vec2::new(away.x.signum(), 0.0),
+                    depth: overlap.x,
+                    away,
+                }
+            } else {
+                Surface {
+                    normal: Vec2::new(0.0, away.y.signum()),
+                    depth: overlap.y,
+                    away,
+                }
+            });
+            if let Some(obstacle) = maybe_obstacle {
+                // gone after the first hit
+                commands.entity(e).remove::<Collider>();
+                hit.obstacles.push(ObstacleHitEvent {
+                    obstacle: e,
+                    kind: obstacle.kind,
+                    position: center,
+                    drops_bonus: obstacle.kind.drops_bonus(),
+                });
+                if hit_by != HitBy::Blast && obstacle.kind.deflects() {
+                    hit.deflect = Some((away.normalize_or_zero(), 1.0));
+                    continue;
+                }
+            }
+            if hit_by != HitBy::Blast && material == Some(BrickMaterial::Awning) {
+                // awnings are flat so always bounce straight up or down
+                hit.deflect = Some((Vec2::new(0.0, away.y.signum()), AWNING_BOUNCE));
+                continue;
+            }
+            hit.any = true;
             if let Some(g) = maybe_gorilla {
-                did_collide_with_gorilla = Some(g.player.clone());
+                hit.gorilla = Some(g.player.clone());
+            }
+            let Some(mut brick) = maybe_building else {
+                hit.solid = true;
+                continue;
+            };
+            if hit_by == HitBy::Touch {
+                continue;
             }
-            if maybe_building.is_some() {
-                // collided with brick
-                // * remove collider, so that they are not blow up anymore
-                // * put a random velocity on them so that they fly around
-                let mut rng = thread_rng();
-                commands.entity(e).remove::<Collider>().insert((
-                    ExplodeBrick::new(rng.gen_range(BRICK_A_STEP_RANGE)),
-                    Velocity(
-                        [
-                            rng.gen_range(BRICK_EXPLODE_STARTING_VELOCITY_RANGE_X),
-                            rng.gen_range(BRICK_EXPLODE_STARTING_VELOCITY_RANGE_Y),
-                        ]
-                        .into(),
-                    ),
-                    MovementState::new(transform.translation.truncate()),
-                ));
+            match brick.hit(banana) {
+                BrickDamage::Broken => {
+                    hit.broken += 1;
+                    // collided with brick
+                    // * remove collider, so that they are not blow up anymore
+                    // * put a random velocity on them so that they fly around
+                    let mut rng = thread_rng();
+                    let speed = brick.material.debris_speed();
+                    let fade = brick.material.debris_fade();
+                    commands.entity(e).remove::<Collider>().insert((
+                        ExplodeBrick::new(rng.gen_range(BRICK_A_STEP_RANGE) * fade),
+                        Velocity(
+                            Vec2::new(
+                                rng.gen_range(BRICK_EXPLODE_STARTING_VELOCITY_RANGE_X),
+                                rng.gen_range(BRICK_EXPLODE_STARTING_VELOCITY_RANGE_Y),
+                            ) * speed,
+                        ),
+                        MovementState::new(transform.translation.truncate()),
+                    ));
+                }
+                BrickDamage::Cracked => {
+                    // darker with every hit it survives
+                    if let Some(mut sprite) = maybe_sprite {
+                        let color = sprite.color;
+                        sprite.color = Color::rgba(
+                            color.r() * CRACKED_DARKEN,
+                            color.g() * CRACKED_DARKEN,
+                            color.b() * CRACKED_DARKEN,
+                            color.a(),
+                        );
+                    }
+                    hit.solid = true;
+                }
+                BrickDamage::None => hit.solid = true,
             }
         }
     }
 
-    (did_collide, did_collide_with_gorilla)
+    hit
 }
 
 fn spawn_explosion(
     mut commands: Commands,
-    query: Query<&Transform, With<Banana>>,
+    query: Query<(&Transform, &Banana, Option<&BigBlast>)>,
     mut banana_collision_event: EventReader<BananaCollisionEvent>,
 ) {
     for event in banana_collision_event.read() {
-        if let Ok(transform) = query.get(event.banana_entity) {
+        if let Ok((transform, banana, big_blast)) = query.get(event.banana_entity) {
             let banana_pos = transform.translation.truncate();
             let shape = shapes::RegularPolygon {
                 sides: 10,
@@ -167,7 +376,15 @@ fn spawn_explosion(
             commands.entity(event.banana_entity).despawn_recursive();
             // add the explosion
             commands.spawn((
-                Explosion,
+                Explosion {
+                    banana: event.banana_entity,
+                    size: banana.kind.def().explosion_size
+                        * if big_blast.is_some() {
+                            BIG_BLAST_SCALE
+                        } else {
+                            1.0
+                        },
+                },
                 (
                     ShapeBundle {
                         path: GeometryBuilder::build_as(&shape),
@@ -183,3 +400,149 @@ fn spawn_explosion(
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::projectile::ProjectileKind;
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app.add_event::<BananaCollisionEvent>()
+            .add_event::<ObstacleHitEvent>()
+            .add_event::<PickupEvent>()
+            .add_systems(Update, check_for_collisions_banana);
+        app
+    }
+
+    fn spawn_brick(app: &mut App, material: BrickMaterial, x: f32, y: f32) -> Entity {
+        app.world
+            .spawn((
+                BuildingBrick::new(material),
+                Transform {
+                    translation: Vec3::new(x, y, 0.0),
+                    scale: Vec3::new(BUILDING_BRICK_WIDTH, BUILDING_BRICK_HEIGHT, 1.0),
+                    ..default()
+                },
+                Sprite::default(),
+                Collider,
+            ))
+            .id()
+    }
+
+    fn spawn_banana(app: &mut App, kind: ProjectileKind, position: Vec2, velocity: Vec2) -> Entity {
+        let def = kind.def();
+        app.world
+            .spawn((
+                Banana {
+                    thrown_by: Player::One,
+                    kind,
+                    bounces_left: def.bounces,
+                    pierces_left: def.pierces,
+                },
+                Transform {
+                    translation: position.extend(0.0),
+                    scale: (BANANA_SCALE * def.size).extend(1.0),
+                    ..default()
+                },
+                Velocity(velocity),
+            ))
+            .id()
+    }
+
+    fn exploded(app: &App) -> bool {
+        !app.world
+            .resource::<Events<BananaCollisionEvent>>()
+            .is_empty()
+    }
+
+    #[test]
+    fn rubber_bounces_off_the_roof_beside_a_wall() {
+        let mut app = app();
+        // a taller building to the left, the banana grazes its side as it lands
+        spawn_brick(&mut app, BrickMaterial::Brick, -20.0, BUILDING_BRICK_HEIGHT);
+        let roof = spawn_brick(&mut app, BrickMaterial::Brick, 0.0, 0.0);
+        let half = BANANA_SCALE / 2.0;
+        let banana = spawn_banana(
+            &mut app,
+            ProjectileKind::Rubber,
+            Vec2::new(-10.0 + half.x - 0.1, 4.0 + half.y - 0.2),
+            Vec2::new(0.0, -50.0),
+        );
+        app.update();
+
+        let velocity = app.world.get::<Velocity>(banana).unwrap();
+        assert!(velocity.y > 0.0, "did not bounce up, {velocity:?}");
+        let bounces = ProjectileKind::Rubber.def().bounces;
+        assert_eq!(
+            app.world.get::<Banana>(banana).unwrap().bounces_left,
+            bounces - 1
+        );
+        assert!(app.world.get::<Collider>(roof).is_some());
+        assert!(!exploded(&app));
+    }
+
+    #[test]
+    fn rubber_wedged_between_bricks_blows_up() {
+        let mut app = app();
+        spawn_brick(&mut app, BrickMaterial::Brick, 0.0, 0.0);
+        spawn_brick(&mut app, BrickMaterial::Brick, BUILDING_BRICK_WIDTH, 0.0);
+        spawn_banana(
+            &mut app,
+            ProjectileKind::Rubber,
+            Vec2::new(BUILDING_BRICK_WIDTH / 2.0, 1.0),
+            Vec2::new(0.0, -50.0),
+        );
+        app.update();
+        assert!(exploded(&app));
+    }
+
+    #[test]
+    fn drill_goes_through_bricks_until_steel() {
+        let mut app = app();
+        let brick = spawn_brick(&mut app, BrickMaterial::Brick, 0.0, 0.0);
+        let steel = spawn_brick(&mut app, BrickMaterial::Steel, 0.0, -BUILDING_BRICK_HEIGHT);
+        let banana = spawn_banana(
+            &mut app,
+            ProjectileKind::Drill,
+            Vec2::ZERO,
+            Vec2::new(0.0, -50.0),
+        );
+        app.update();
+        assert!(app.world.get::<Collider>(brick).is_none());
+        let pierces = ProjectileKind::Drill.def().pierces;
+        assert_eq!(
+            app.world.get::<Banana>(banana).unwrap().pierces_left,
+            pierces - 1
+        );
+        assert!(!exploded(&app));
+
+        app.world
+            .get_mut::<Transform>(banana)
+            .unwrap()
+            .translation
+            .y = -BUILDING_BRICK_HEIGHT;
+        app.update();
+        assert!(app.world.get::<Collider>(steel).is_some());
+        assert!(exploded(&app));
+    }
+
+    #[test]
+    fn concrete_counts_each_banana_once() {
+        let mut brick = BuildingBrick::new(BrickMaterial::Concrete);
+        let first = Entity::from_raw(1);
+        let second = Entity::from_raw(2);
+        // two blasts overlapping take turns hitting it every frame
+        let damage: Vec<_> = (0..10)
+            .map(|i| brick.hit(if i % 2 == 0 { first } else { second }))
+            .collect();
+        assert_eq!(damage[..2], [BrickDamage::Cracked, BrickDamage::Cracked]);
+        assert!(damage[2..].iter().all(|d| *d == BrickDamage::None));
+        assert_eq!(
+            brick.hit(Entity::from_raw(3)),
+            BrickDamage::Broken,
+            "the third banana breaks it"
+        );
+    }
+}
@@ -1,3 +1,4 @@
+use bevy::prelude::{Color, Vec2};
 use std::ops::Range;
 
 // Defines the amount of time that should elapse between each physics step.
@@ -9,8 +10,17 @@ pub const PIXEL_STEP_SIZE: f32 = 20.0;
 pub const BUILDING_WIDTH: f32 = 160.0;
 pub const BUILDING_BRICK_WIDTH: f32 = 20.0;
 pub const BUILDING_BRICK_HEIGHT: f32 = 8.0;
-pub const SCREEN_WIDTH: f32 = 1280.0;
-pub const SCREEN_HEIGHT: f32 = 720.0;
+pub const WINDOW_WIDTH: f32 = 1280.0;
+pub const WINDOW_HEIGHT: f32 = 720.0;
+pub const ARENA_WIDTH: f32 = 1280.0;
+pub const ARENA_HEIGHT: f32 = 720.0;
+// the most of the arena the camera shows up close, bigger arenas scroll
+pub const VIEW_WIDTH: f32 = 1280.0;
+pub const VIEW_HEIGHT: f32 = 720.0;
+pub const CAMERA_MARGIN: f32 = 100.0;
+pub const SUN_RADIUS: f32 = 30.0;
+pub const SUN_RAY_LENGTH: f32 = 14.0;
+pub const SUN_TOP_MARGIN: f32 = 80.0;
 pub const BANANA_WIDTH: f32 = 32.0;
 pub const BANANA_HEIGHT: f32 = 32.0;
 pub const GORILLA_HEIGHT: f32 = 64.0;
@@ -18,18 +28,57 @@ pub const GORILLA_WIDTH: f32 = 32.0;
 pub const EXPLOSION_START_RADIUS: f32 = BANANA_WIDTH / 2.0;
 pub const EXPLOSION_START_DIAMETER: f32 = EXPLOSION_START_RADIUS * 2.0;
 pub const EXPLOSION_SIZE: f32 = 3.0;
+pub const BANANA_WATCH_DISTANCE: f32 = 50.0;
+pub const TRAJECTORY_DOT_SIZE: f32 = 4.0;
+pub const WINDOW_ROWS: usize = 3;
+pub const LIT_WINDOW_CHANCE: f64 = 0.4;
+pub const WEATHER_PARTICLES: usize = 300; // in an arena the size of the view
+pub const MAX_WEATHER_PARTICLES: usize = 1000;
 pub const BRICK_A_STEP_RANGE: Range<f32> = 0.002..0.008;
+pub const CONCRETE_HITS: u8 = 3;
+pub const GLASS_SHATTER_DISTANCE: f32 = 40.0;
+pub const AWNING_BOUNCE: f32 = 0.7; // how much of its speed a bouncing banana keeps
+pub const CRACKED_DARKEN: f32 = 0.8;
+pub const CLUSTER_FRAGMENTS: usize = 3;
+pub const CRATE_SIZE: f32 = 20.0;
+pub const WALL_WIDTH: f32 = BUILDING_BRICK_WIDTH * 2.0;
+pub const WALL_HEIGHT: f32 = BUILDING_BRICK_HEIGHT * 10.0;
+pub const WALL_GAP: f32 = 10.0; // between a gorilla and the wall they build
+pub const BIG_BLAST_SCALE: f32 = 2.0;
+pub const RUBBER_BOUNCE: f32 = 0.8; // how much of its speed a rubber banana keeps
+pub const TURN_WARNING_SECONDS: f32 = 5.0; // left on the turn timer when it warns
+
+// Colors
+pub const WINDOW_LIT_COLOR: Color = Color::rgb(1.0, 1.0, 0.33);
+pub const WINDOW_DARK_COLOR: Color = Color::rgb(0.25, 0.25, 0.25);
+pub const CONCRETE_COLOR: Color = Color::rgb(0.6, 0.6, 0.58);
+pub const GLASS_COLOR: Color = Color::rgba(0.6, 0.85, 0.95, 0.8);
+pub const STEEL_COLOR: Color = Color::rgb(0.3, 0.35, 0.42);
+pub const AWNING_COLOR: Color = Color::rgb(0.8, 0.15, 0.15);
+pub const SUN_COLOR: Color = Color::rgb(1.0, 0.85, 0.1);
+pub const SHIELD_COLOR: Color = Color::rgb(0.4, 0.9, 1.0);
 
 // Speeds
 pub const EXPLOSION_SPEED: f32 = 4.0 * (64.0 / FIXED_HZ as f32); // the more hz the slower
 pub const GRAVITY_Y_ACCEL: f32 = -9.8 * PIXEL_STEP_SIZE;
+pub const MAX_WIND: i32 = 20;
+pub const CLUSTER_SPREAD: f32 = 60.0; // sideways speed between cluster fragments
+pub const GORILLA_WALK_SPEED: f32 = 60.0;
+pub const GORILLA_WALK_DISTANCE: f32 = BUILDING_BRICK_WIDTH * 3.0; // each turn
+pub const GORILLA_JUMP: Vec2 = Vec2::new(60.0, 250.0);
+pub const CAMERA_EASING: f32 = 4.0; // per second, higher catches up faster
 pub const BRICK_EXPLODE_STARTING_VELOCITY_RANGE_X: Range<f32> = -100.0..100.0;
 pub const BRICK_EXPLODE_STARTING_VELOCITY_RANGE_Y: Range<f32> = 100.0..400.0;
 
 // Z index
+pub const SUN_Z_INDEX: f32 = 0.5; // in front of the sky, behind the buildings
 pub const BUILDING_Z_INDEX: f32 = 1.0;
 pub const BANANA_Z_INDEX: f32 = 4.0;
+pub const CRATE_Z_INDEX: f32 = 7.0;
+pub const OBSTACLE_Z_INDEX: f32 = 8.0;
 pub const GORILLA_Z_INDEX: f32 = 10.0;
+pub const TRAJECTORY_Z_INDEX: f32 = 11.0;
 pub const THROW_IND_Z_INDEX: f32 = 12.0;
 pub const EXPLOSION_Z_INDEX: f32 = 15.0;
+pub const WEATHER_Z_INDEX: f32 = 18.0;
 pub const WIND_Z_INDEX: f32 = 20.0;
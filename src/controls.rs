@@ -0,0 +1,435 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use bevy::asset::io::file::FileAssetReader;
+use bevy::input::InputSystem;
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+
+const CONTROLS_FILE: &str = "controls.ron";
+// how far a stick has to be pushed before it counts as pressed
+const STICK_DEAD_ZONE: f32 = 0.5;
+
+/// a file in `assets/config`, found the same way the asset server finds the assets so it
+/// does not matter where the game is started from
+pub(crate) fn config_path(file: &str) -> PathBuf {
+    FileAssetReader::get_base_path()
+        .join("assets")
+        .join("config")
+        .join(file)
+}
+
+/// Logical actions, what a key or button means to the game rather than which one it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub(crate) enum GameAction {
+    AimUp,
+    AimDown,
+    PowerUp,
+    PowerDown,
+    Fire,
+    Pause,
+    ChangeWind,
+    Overview,
+    NextWeapon,
+    NextTurnAction,
+    WalkLeft,
+    WalkRight,
+    Jump,
+    /// show the plan that is otherwise hidden while planning in secret
+    Peek,
+    Mute,
+    VolumeUp,
+    VolumeDown,
+}
+
+/// How a player picks their angle and speed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum AimMode {
+    /// nudge the throw with the aim and power actions
+    #[default]
+    Arrows,
+    /// type the angle and velocity like the original game
+    Typed,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct AxisBinding {
+    axis: GamepadAxisType,
+    positive: bool,
+}
+
+/// Everything that one player can use to control their gorilla
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub(crate) struct Bindings {
+    aim: AimMode,
+    /// which connected controller belongs to this player, if any
+    gamepad: Option<usize>,
+    keys: HashMap<GameAction, Vec<KeyCode>>,
+    buttons: HashMap<GameAction, Vec<GamepadButtonType>>,
+    axes: HashMap<GameAction, Vec<AxisBinding>>,
+}
+
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Controls {
+    player_one: Bindings,
+    player_two: Bindings,
+}
+
+impl Controls {
+    fn load() -> Self {
+        let path = config_path(CONTROLS_FILE);
+        match fs::read_to_string(&path) {
+            Ok(contents) => match ron::from_str(&contents) {
+                Ok(controls) => controls,
+                Err(e) => {
+                    error!(
+                        "unable to parse {}, using default controls: {e}",
+                        path.display()
+                    );
+                    Controls::default()
+                }
+            },
+            Err(e) => {
+                warn!(
+                    "unable to read {}, using default controls: {e}",
+                    path.display()
+                );
+                Controls::default()
+            }
+        }
+    }
+
+    pub(crate) fn aim_mode(&self, player: &Player) -> AimMode {
+        self.bindings(player).aim
+    }
+
+    fn bindings(&self, player: &Player) -> &Bindings {
+        match player {
+            Player::One => &self.player_one,
+            Player::Two => &self.player_two,
+        }
+    }
+}
+
+impl Default for Controls {
+    fn default() -> Self {
+        // players take turns so they can share the keyboard, and each get a controller
+        let keys = HashMap::from([
+            (GameAction::AimUp, vec![KeyCode::ArrowUp]),
+            (GameAction::AimDown, vec![KeyCode::ArrowDown]),
+            (GameAction::PowerUp, vec![KeyCode::ArrowRight]),
+            (GameAction::PowerDown, vec![KeyCode::ArrowLeft]),
+            (GameAction::Fire, vec![KeyCode::Space]),
+            (GameAction::Pause, vec![KeyCode::KeyP]),
+            (GameAction::ChangeWind, vec![KeyCode::KeyW]),
+            (GameAction::Overview, vec![KeyCode::KeyZ]),
+            (GameAction::NextWeapon, vec![KeyCode::KeyQ]),
+            (GameAction::NextTurnAction, vec![KeyCode::KeyE]),
+            (GameAction::WalkLeft, vec![KeyCode::KeyA]),
+            (GameAction::WalkRight, vec![KeyCode::KeyD]),
+            (GameAction::Jump, vec![KeyCode::KeyJ]),
+            (GameAction::Peek, vec![KeyCode::KeyV]),
+            (GameAction::Mute, vec![KeyCode::KeyM]),
+            (GameAction::VolumeUp, vec![KeyCode::Equal]),
+            (GameAction::VolumeDown, vec![KeyCode::Minus]),
+        ]);
+        let buttons = HashMap::from([
+            (GameAction::AimUp, vec![GamepadButtonType::DPadUp]),
+            (GameAction::AimDown, vec![GamepadButtonType::DPadDown]),
+            (GameAction::PowerUp, vec![GamepadButtonType::DPadRight]),
+            (GameAction::PowerDown, vec![GamepadButtonType::DPadLeft]),
+            (GameAction::Fire, vec![GamepadButtonType::South]),
+            (GameAction::Pause, vec![GamepadButtonType::Start]),
+            (GameAction::Overview, vec![GamepadButtonType::North]),
+            (GameAction::NextWeapon, vec![GamepadButtonType::West]),
+            (GameAction::NextTurnAction, vec![GamepadButtonType::East]),
+            (GameAction::WalkLeft, vec![GamepadButtonType::LeftTrigger]),
+            (GameAction::WalkRight, vec![GamepadButtonType::RightTrigger]),
+            (GameAction::Jump, vec![GamepadButtonType::RightTrigger2]),
+            (GameAction::Peek, vec![GamepadButtonType::Select]),
+        ]);
+        let axes = HashMap::from([
+            (
+                GameAction::AimUp,
+                vec![AxisBinding {
+                    axis: GamepadAxisType::LeftStickY,
+                    positive: true,
+                }],
+            ),
+            (
+                GameAction::AimDown,
+                vec![AxisBinding {
+                    axis: GamepadAxisType::LeftStickY,
+                    positive: false,
+                }],
+            ),
+            (
+                GameAction::PowerUp,
+                vec![AxisBinding {
+                    axis: GamepadAxisType::LeftStickX,
+                    positive: true,
+                }],
+            ),
+            (
+                GameAction::PowerDown,
+                vec![AxisBinding {
+                    axis: GamepadAxisType::LeftStickX,
+                    positive: false,
+                }],
+            ),
+        ]);
+        Controls {
+            player_one: Bindings {
+                aim: AimMode::Arrows,
+                gamepad: Some(0),
+                keys: keys.clone(),
+                buttons: buttons.clone(),
+                axes: axes.clone(),
+            },
+            player_two: Bindings {
+                aim: AimMode::Arrows,
+                gamepad: Some(1),
+                keys,
+                buttons,
+                axes,
+            },
+        }
+    }
+}
+
+/// The logical actions each player is pressing, rebuilt every frame from the bindings
+#[derive(Resource, Default)]
+pub(crate) struct PlayerActions {
+    one: ButtonInput<GameAction>,
+    two: ButtonInput<GameAction>,
+}
+
+impl PlayerActions {
+    pub(crate) fn get(&self, player: &Player) -> &ButtonInput<GameAction> {
+        match player {
+            Player::One => &self.one,
+            Player::Two => &self.two,
+        }
+    }
+
+    pub(crate) fn get_mut(&mut self, player: &Player) -> &mut ButtonInput<GameAction> {
+        match player {
+            Player::One => &mut self.one,
+            Player::Two => &mut self.two,
+        }
+    }
+
+    pub(crate) fn any_just_pressed(&self, action: GameAction) -> bool {
+        self.one.just_pressed(action) || self.two.just_pressed(action)
+    }
+}
+
+/// run condition for an action of the player whose turn it is
+pub(crate) fn action_just_pressed(
+    action: GameAction,
+) -> impl FnMut(Res<PlayerActions>, Res<State<Player>>) -> bool + Clone {
+    move |actions: Res<PlayerActions>, player: Res<State<Player>>| {
+        actions.get(player.get()).just_pressed(action)
+    }
+}
+
+/// run condition for an action that anyone is allowed to do
+pub(crate) fn any_action_just_pressed(
+    action: GameAction,
+) -> impl FnMut(Res<PlayerActions>) -> bool + Clone {
+    move |actions: Res<PlayerActions>| actions.any_just_pressed(action)
+}
+
+/// run condition for the player whose turn it is aiming a certain way
+pub(crate) fn aim_mode_is(
+    mode: AimMode,
+) -> impl FnMut(Res<Controls>, Res<State<Player>>) -> bool + Clone {
+    move |controls: Res<Controls>, player: Res<State<Player>>| {
+        controls.aim_mode(player.get()) == mode
+    }
+}
+
+pub(crate) struct ControlsPlugin;
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Controls::load())
+            .init_resource::<PlayerActions>()
+            .add_systems(PreUpdate, update_player_actions.after(InputSystem))
+            .add_systems(
+                Update,
+                toggle_pause.run_if(any_action_just_pressed(GameAction::Pause)),
+            );
+    }
+}
+
+fn update_player_actions(
+    controls: Res<Controls>,
+    time: Res<Time<Virtual>>,
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    gamepads: Res<Gamepads>,
+    mut actions: ResMut<PlayerActions>,
+) {
+    for player in [Player::One, Player::Two] {
+        let bindings = controls.bindings(&player);
+        let gamepad = bindings
+            .gamepad
+            .map(Gamepad::new)
+            .filter(|g| gamepads.contains(*g));
+        let input = actions.get_mut(&player);
+        input.clear();
+
+        let mut pressed: Vec<GameAction> = vec![];
+        for (action, keys) in bindings.keys.iter() {
+            if keyboard_input.any_pressed(keys.iter().copied()) {
+                pressed.push(*action);
+            }
+        }
+        if let Some(gamepad) = gamepad {
+            for (action, buttons) in bindings.buttons.iter() {
+                if gamepad_buttons
+                    .any_pressed(buttons.iter().map(|b| GamepadButton::new(gamepad, *b)))
+                {
+                    pressed.push(*action);
+                }
+            }
+            for (action, axes) in bindings.axes.iter() {
+                let pushed = axes.iter().any(|a| {
+                    let value = gamepad_axes
+                        .get(GamepadAxis::new(gamepad, a.axis))
+                        .unwrap_or(0.0);
+                    if a.positive {
+                        value > STICK_DEAD_ZONE
+                    } else {
+                        value < -STICK_DEAD_ZONE
+                    }
+                });
+                if pushed {
+                    pressed.push(*action);
+                }
+            }
+        }
+
+        // while paused the only thing you can do is unpause, or turn the sound down
+        if time.is_paused() {
+            pressed.retain(|a| {
+                matches!(
+                    a,
+                    GameAction::Pause
+                        | GameAction::Mute
+                        | GameAction::VolumeUp
+                        | GameAction::VolumeDown
+                )
+            });
+        }
+
+        for action in [
+            GameAction::AimUp,
+            GameAction::AimDown,
+            GameAction::PowerUp,
+            GameAction::PowerDown,
+            GameAction::Fire,
+            GameAction::Pause,
+            GameAction::ChangeWind,
+            GameAction::Overview,
+            GameAction::NextWeapon,
+            GameAction::NextTurnAction,
+            GameAction::WalkLeft,
+            GameAction::WalkRight,
+            GameAction::Jump,
+            GameAction::Peek,
+            GameAction::Mute,
+            GameAction::VolumeUp,
+            GameAction::VolumeDown,
+        ] {
+            if pressed.contains(&action) {
+                input.press(action);
+            } else {
+                input.release(action);
+            }
+        }
+    }
+}
+
+fn toggle_pause(mut time: ResMut<Time<Virtual>>) {
+    if time.is_paused() {
+        info!("unpause");
+        time.unpause();
+    } else {
+        info!("pause");
+        time.pause();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// run the bindings once with `keys` held down
+    fn actions_for(controls: Controls, keys: &[KeyCode], paused: bool) -> PlayerActions {
+        let mut app = App::new();
+        let mut time = Time::<Virtual>::default();
+        if paused {
+            time.pause();
+        }
+        let mut keyboard_input = ButtonInput::<KeyCode>::default();
+        for key in keys {
+            keyboard_input.press(*key);
+        }
+        app.insert_resource(controls)
+            .insert_resource(time)
+            .insert_resource(keyboard_input)
+            .init_resource::<ButtonInput<GamepadButton>>()
+            .init_resource::<Axis<GamepadAxis>>()
+            .init_resource::<Gamepads>()
+            .init_resource::<PlayerActions>()
+            .add_systems(Update, update_player_actions);
+        app.update();
+        app.world.remove_resource::<PlayerActions>().unwrap()
+    }
+
+    #[test]
+    fn shipped_controls_are_found_and_parse() {
+        let path = config_path(CONTROLS_FILE);
+        let contents = fs::read_to_string(&path).expect("controls.ron next to the assets");
+        let controls: Controls = ron::from_str(&contents).unwrap();
+        assert_eq!(controls.aim_mode(&Player::One), AimMode::Arrows);
+    }
+
+    #[test]
+    fn keys_are_shared_by_default() {
+        let actions = actions_for(Controls::default(), &[KeyCode::Space], false);
+        for player in [Player::One, Player::Two] {
+            assert!(actions.get(&player).just_pressed(GameAction::Fire));
+            assert!(!actions.get(&player).pressed(GameAction::AimUp));
+        }
+    }
+
+    #[test]
+    fn each_player_has_their_own_bindings() {
+        let mut controls = Controls::default();
+        controls
+            .player_two
+            .keys
+            .insert(GameAction::Fire, vec![KeyCode::Enter]);
+        let actions = actions_for(controls, &[KeyCode::Enter], false);
+        assert!(actions.get(&Player::Two).just_pressed(GameAction::Fire));
+        assert!(!actions.get(&Player::One).pressed(GameAction::Fire));
+    }
+
+    #[test]
+    fn only_unpause_and_volume_while_paused() {
+        let actions = actions_for(
+            Controls::default(),
+            &[KeyCode::Space, KeyCode::KeyP, KeyCode::KeyM],
+            true,
+        );
+        assert!(!actions.any_just_pressed(GameAction::Fire));
+        assert!(actions.any_just_pressed(GameAction::Pause));
+        assert!(actions.any_just_pressed(GameAction::Mute));
+    }
+}
@@ -0,0 +1,250 @@
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+
+use crate::controls::{action_just_pressed, GameAction};
+use crate::game::{spawn_building, Action, Explosion, InGameplaySet};
+use crate::level::{BrickMaterial, BrickPattern, BuildingDef, Rgb};
+use crate::movement::no_one_airborne;
+use crate::prelude::*;
+use crate::rules::MatchRules;
+
+const WALL_COLOR: Rgb = Rgb(150, 80, 60);
+const WALL_MORTAR: Rgb = Rgb(120, 110, 100);
+
+/// What the player is going to do with their turn, only used while in `Action::Enter`
+#[derive(Debug, States, Clone, Copy, Hash, Default, PartialEq, Eq)]
+pub(crate) enum TurnAction {
+    #[default]
+    Throw,
+    /// raise a shield that lasts until their next turn
+    Shield,
+    /// build a short wall in front of themselves
+    Wall,
+}
+
+impl TurnAction {
+    fn next(self) -> TurnAction {
+        match self {
+            TurnAction::Throw => TurnAction::Shield,
+            TurnAction::Shield => TurnAction::Wall,
+            TurnAction::Wall => TurnAction::Throw,
+        }
+    }
+}
+
+/// The turn was used up without throwing anything
+#[derive(Event)]
+pub(crate) struct TurnSpentEvent;
+
+/// Keeps a gorilla safe from explosions
+#[derive(Component)]
+pub(crate) struct Shield {
+    /// the explosion it stopped, the shield is gone once that is over
+    pub(crate) absorbing: Option<Entity>,
+    /// gone when its gorilla's turn comes around again even if it stopped nothing
+    one_turn: bool,
+}
+
+impl Shield {
+    pub(crate) fn until_used() -> Self {
+        Shield {
+            absorbing: None,
+            one_turn: false,
+        }
+    }
+
+    fn for_one_turn() -> Self {
+        Shield {
+            absorbing: None,
+            one_turn: true,
+        }
+    }
+
+    /// it stops one explosion, any other that goes off while that one is still going
+    /// gets through
+    pub(crate) fn stops(&self, explosion: Entity) -> bool {
+        self.absorbing.is_none() || self.absorbing == Some(explosion)
+    }
+}
+
+#[derive(Component)]
+struct ShieldBubble;
+
+pub(crate) struct DefensePlugin;
+impl Plugin for DefensePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_state::<TurnAction>()
+            .add_event::<TurnSpentEvent>()
+            .add_systems(OnEnter(Action::Enter), reset_turn_action)
+            .add_systems(
+                Update,
+                (
+                    next_turn_action.run_if(action_just_pressed(GameAction::NextTurnAction)),
+                    // walls go up from the roof they stand on, not from mid air
+                    defend
+                        .run_if(action_just_pressed(GameAction::Fire))
+                        .run_if(not(in_state(TurnAction::Throw)))
+                        .run_if(no_one_airborne),
+                )
+                    // a turn planned in secret can only be a throw
+                    .run_if(in_state(Action::Enter))
+                    .in_set(InGameplaySet::Gorillas),
+            )
+            .add_systems(
+                Update,
+                (
+                    drop_spent_shields,
+                    drop_expired_shields.run_if(state_changed::<Player>),
+                ),
+            );
+    }
+}
+
+fn reset_turn_action(mut next_turn_action: ResMut<NextState<TurnAction>>) {
+    next_turn_action.set(TurnAction::Throw);
+}
+
+fn next_turn_action(
+    turn_action: Res<State<TurnAction>>,
+    mut next_turn_action: ResMut<NextState<TurnAction>>,
+) {
+    next_turn_action.set(turn_action.next());
+}
+
+fn defend(
+    mut commands: Commands,
+    turn_action: Res<State<TurnAction>>,
+    player: Res<State<Player>>,
+    mut next_action: ResMut<NextState<Action>>,
+    mut turn_spent: EventWriter<TurnSpentEvent>,
+    rules: Res<MatchRules>,
+    gorilla_query: Query<(Entity, &Gorilla, &Transform, Option<&Shield>)>,
+) {
+    let Some((gorilla, g, transform, shield)) = gorilla_query
+        .iter()
+        .find(|(_, g, _, _)| &g.player == player.get())
+    else {
+        return;
+    };
+    match turn_action.get() {
+        TurnAction::Throw => return,
+        TurnAction::Shield if shield.is_some() => {
+            info!("{:?} is already shielded", g.player);
+            return;
+        }
+        TurnAction::Shield => {
+            info!("{:?} raised a shield", g.player);
+            raise_shield(&mut commands, gorilla, Shield::for_one_turn());
+        }
+        TurnAction::Wall => {
+            info!("{:?} built a wall", g.player);
+            // the same seed builds the same walls
+            let mut rng = StdRng::seed_from_u64(rules.seed);
+            build_wall(
+                &mut commands,
+                &g.player,
+                transform.translation.truncate(),
+                &mut rng,
+            );
+        }
+    }
+    turn_spent.send(TurnSpentEvent);
+    next_action.set(Action::Watching);
+}
+
+/// a few bricks on the roof just in front of the gorilla, facing the other gorilla
+fn build_wall(commands: &mut Commands, player: &Player, gorilla: Vec2, rng: &mut StdRng) {
+    let direction = match player {
+        Player::One => 1.0,
+        Player::Two => -1.0,
+    };
+    let wall = BuildingDef {
+        x: 0.0,
+        width: WALL_WIDTH,
+        height: WALL_HEIGHT,
+        color: WALL_COLOR,
+        bricks: BrickPattern::Stripes(WALL_MORTAR),
+        windows: false,
+        material: BrickMaterial::Brick,
+        awning: false,
+    };
+    let feet = gorilla.y - GORILLA_HEIGHT / 2.0;
+    spawn_building(
+        format!("{:?} wall", player),
+        commands,
+        &wall,
+        gorilla.x + direction * (GORILLA_WIDTH / 2.0 + WALL_GAP + WALL_WIDTH / 2.0),
+        feet + WALL_HEIGHT / 2.0,
+        rng,
+        0.0,
+    );
+}
+
+pub(crate) fn raise_shield(commands: &mut Commands, gorilla: Entity, shield: Shield) {
+    let mut color = SHIELD_COLOR;
+    color.set_a(0.35);
+    commands
+        .entity(gorilla)
+        .insert(shield)
+        .with_children(|parent| {
+            // drawn in the gorilla's own scale so it stretches around it
+            parent.spawn((
+                ShieldBubble,
+                ShapeBundle {
+                    path: GeometryBuilder::build_as(&shapes::Circle {
+                        radius: 0.8,
+                        center: Vec2::ZERO,
+                    }),
+                    spatial: SpatialBundle::from_transform(Transform::from_xyz(0.0, 0.0, 0.5)),
+                    ..default()
+                },
+                Fill::color(color),
+            ));
+        });
+}
+
+fn lower_shield(
+    commands: &mut Commands,
+    gorilla: Entity,
+    children: &Children,
+    bubble_query: &Query<(), With<ShieldBubble>>,
+) {
+    commands.entity(gorilla).remove::<Shield>();
+    for child in children.iter().filter(|c| bubble_query.contains(**c)) {
+        commands.entity(*child).despawn_recursive();
+    }
+}
+
+/// a shield only lasts until the explosion it stopped is over
+fn drop_spent_shields(
+    mut commands: Commands,
+    shield_query: Query<(Entity, &Shield, &Children)>,
+    explosion_query: Query<(), With<Explosion>>,
+    bubble_query: Query<(), With<ShieldBubble>>,
+) {
+    for (gorilla, shield, children) in shield_query.iter() {
+        if shield
+            .absorbing
+            .is_some_and(|e| explosion_query.get(e).is_err())
+        {
+            info!("shield is gone");
+            lower_shield(&mut commands, gorilla, children, &bubble_query);
+        }
+    }
+}
+
+/// shields raised instead of throwing only last until their gorilla's next turn
+fn drop_expired_shields(
+    mut commands: Commands,
+    player: Res<State<Player>>,
+    shield_query: Query<(Entity, &Gorilla, &Shield, &Children)>,
+    bubble_query: Query<(), With<ShieldBubble>>,
+) {
+    for (gorilla, g, shield, children) in shield_query.iter() {
+        // still protecting them from an explosion that is going on
+        if &g.player == player.get() && shield.one_turn && shield.absorbing.is_none() {
+            info!("{:?} lowered their shield", g.player);
+            lower_shield(&mut commands, gorilla, children, &bubble_query);
+        }
+    }
+}
@@ -1,21 +1,78 @@
-#![allow(clippy::type_complexity)]
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
 
 use iyes_perf_ui::prelude::*;
 use std::cmp;
 use std::f32::consts::PI;
 use std::time::Duration;
 
-use rand::seq::SliceRandom;
-use rand::{thread_rng, RngCore};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
 
+use crate::aim::{DragAimPlugin, TypedAim, TypedAimPlugin, TypedField};
 use crate::arrow;
+use crate::audio::{PlaySoundEvent, Sound};
+use crate::camera::CameraPlugin;
+use crate::controls::{aim_mode_is, AimMode, Controls, ControlsPlugin, GameAction, PlayerActions};
+use crate::defense::{DefensePlugin, Shield, TurnAction, TurnSpentEvent};
+use crate::level::{BrickMaterial, BuildingDef, Level};
+use crate::movement::{GorillaFellEvent, MovementPlugin, Stride};
+use crate::obstacles::ObstaclesPlugin;
 use crate::physics::PhysicsPlugin;
+use crate::pickups::{PickupsPlugin, PowerUps};
+use crate::planning::PlanningPlugin;
 use crate::players::PlayersPlugin;
 use crate::prelude::*;
+use crate::projectile::{Ammo, ProjectilePlugin, Weapon};
+use crate::rules::MatchRules;
+use crate::sun::SunPlugin;
+use crate::theme::{Theme, ThemePlugin};
+use crate::trajectory::TrajectoryPlugin;
+use crate::turn_timer::{TurnClock, TurnTimerPlugin};
+use crate::weather::WeatherPlugin;
 use crate::wind::WindPlugin;
 
 #[derive(Component)]
-pub(crate) struct BuildingBrick;
+pub(crate) struct BuildingBrick {
+    pub(crate) material: BrickMaterial,
+    /// hits left before it breaks, `None` if it never does
+    hits_left: Option<u8>,
+    /// every banana that has hit it, so each one only counts once however its blast
+    /// overlaps with others
+    hit_by: Vec<Entity>,
+}
+
+impl BuildingBrick {
+    pub(crate) fn new(material: BrickMaterial) -> Self {
+        BuildingBrick {
+            material,
+            hits_left: material.hits(),
+            hit_by: Vec::new(),
+        }
+    }
+
+    /// take a hit from a banana or its explosion
+    pub(crate) fn hit(&mut self, banana: Entity) -> BrickDamage {
+        if self.hit_by.contains(&banana) {
+            return BrickDamage::None;
+        }
+        self.hit_by.push(banana);
+        match self.hits_left {
+            Some(hits) if hits <= 1 => BrickDamage::Broken,
+            Some(hits) => {
+                self.hits_left = Some(hits - 1);
+                BrickDamage::Cracked
+            }
+            None => BrickDamage::None,
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum BrickDamage {
+    None,
+    Cracked,
+    Broken,
+}
 
 #[derive(Component)]
 pub(crate) struct ExplodeBrick {
@@ -48,9 +105,13 @@ struct LeftBoard;
 pub(crate) enum Action {
     #[default]
     Enter,
+    /// everyone takes a turn to plan their throw in secret, then they all fly at once
+    Planning,
     Throwing,
     Watching,
     Winner,
+    /// nobody has anything left to throw, or both gorillas went down together
+    Draw,
 }
 
 #[derive(Component)]
@@ -62,6 +123,12 @@ pub(crate) struct AngleSpeed {
     pub(crate) speed: u8,
 }
 
+impl AngleSpeed {
+    pub(crate) const MAX_ANGLE: u8 = 230;
+    pub(crate) const MIN_SPEED: u8 = 10;
+    pub(crate) const MAX_SPEED: u8 = 200;
+}
+
 impl Default for AngleSpeed {
     fn default() -> Self {
         AngleSpeed {
@@ -72,7 +139,12 @@ impl Default for AngleSpeed {
 }
 
 #[derive(Component)]
-pub(crate) struct Explosion;
+pub(crate) struct Explosion {
+    /// the banana that blew up
+    pub(crate) banana: Entity,
+    /// how big it grows before it is done
+    pub(crate) size: f32,
+}
 
 #[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
 pub(crate) enum InGameplaySet {
@@ -91,8 +163,8 @@ impl Plugin for GamePlugin {
         app.add_plugins(DefaultPlugins.set(WindowPlugin {
             primary_window: Some(Window {
                 title: "Gorillas".to_string(),
-                resolution: (SCREEN_WIDTH, SCREEN_HEIGHT).into(),
-                resizable: false,
+                resolution: (WINDOW_WIDTH, WINDOW_HEIGHT).into(),
+                resizable: true,
                 ..default()
             }),
             ..default()
@@ -104,15 +176,29 @@ impl Plugin for GamePlugin {
         .add_plugins(bevy::diagnostic::SystemInformationDiagnosticsPlugin)
         .add_plugins(PerfUiPlugin)
         // objects and colors
-        .insert_resource(ClearColor(Color::rgb_u8(126, 161, 219)))
         .add_plugins(ShapePlugin)
+        .add_plugins(ThemePlugin)
+        .add_plugins(WeatherPlugin)
         // our plugins
+        .add_plugins(ControlsPlugin)
+        .add_plugins(CameraPlugin)
         .add_plugins(audio::GorillasAudioPlugin)
         .add_plugins(CollisionPlugin)
         .add_plugins(PlayersPlugin)
         .add_plugins(PhysicsPlugin)
         .add_plugins(WindPlugin)
+        .add_plugins(SunPlugin)
+        .add_plugins(ObstaclesPlugin)
+        .add_plugins(PickupsPlugin)
+        .add_plugins(DefensePlugin)
+        .add_plugins(MovementPlugin)
+        .add_plugins(PlanningPlugin)
+        .add_plugins(TurnTimerPlugin)
         .add_plugins(BananaPlugin)
+        .add_plugins(ProjectilePlugin)
+        .add_plugins(TrajectoryPlugin)
+        .add_plugins(DragAimPlugin)
+        .add_plugins(TypedAimPlugin)
         .init_state::<Action>()
         // Startup
         .add_systems(Startup, (setup, setup_arena))
@@ -120,7 +206,8 @@ impl Plugin for GamePlugin {
         .configure_sets(
             Update,
             (
-                InGameplaySet::Gorillas.run_if(in_state(Action::Enter)),
+                InGameplaySet::Gorillas
+                    .run_if(in_state(Action::Enter).or_else(in_state(Action::Planning))),
                 InGameplaySet::TurnChanges.after(InGameplaySet::Collisions),
             ),
         )
@@ -129,37 +216,60 @@ impl Plugin for GamePlugin {
             Update,
             (
                 (state_logger, update_text_left).in_set(InGameplaySet::Watchers),
-                (throw_indicator, rotate_and_change_velocity_input).in_set(InGameplaySet::Gorillas),
                 (
-                    next_player_system.run_if(in_state(Action::Watching)),
-                    winner_player_system,
+                    throw_indicator,
+                    // the typed fields are the only way to change a typed aim
+                    rotate_and_change_velocity_input.run_if(aim_mode_is(AimMode::Arrows)),
+                )
+                    .in_set(InGameplaySet::Gorillas),
+                (
+                    // the last banana can take out a gorilla as it resolves, that decides
+                    // the winner rather than whose turn is next
+                    (
+                        next_player_system
+                            .run_if(in_state(Action::Watching))
+                            .run_if(no_gorilla_down),
+                        winner_player_system,
+                    )
+                        .chain(),
+                    out_of_ammo_system
+                        .run_if(in_state(Action::Enter).or_else(in_state(Action::Planning))),
                 )
                     .in_set(InGameplaySet::TurnChanges),
             ),
         )
         .add_systems(OnEnter(Action::Enter), spawn_throw_indicator)
         .add_systems(OnExit(Action::Throwing), cleanup_system::<ThrowIndicator>)
+        // turns spent defending go straight to watching without throwing
+        .add_systems(
+            OnTransition {
+                from: Action::Enter,
+                to: Action::Watching,
+            },
+            cleanup_system::<ThrowIndicator>,
+        )
+        // it would give away the plan
+        .add_systems(OnEnter(Action::Planning), cleanup_system::<ThrowIndicator>)
         .add_systems(OnEnter(Action::Winner), cleanup_system::<ThrowIndicator>)
+        .add_systems(OnEnter(Action::Draw), cleanup_system::<ThrowIndicator>)
         .add_systems(Update, bevy::window::close_on_esc);
     }
 }
 
-fn cleanup_system<T: Component>(mut commands: Commands, q: Query<Entity, With<T>>) {
+pub(crate) fn cleanup_system<T: Component>(mut commands: Commands, q: Query<Entity, With<T>>) {
     for e in q.iter() {
         commands.entity(e).despawn_recursive();
     }
 }
 
-fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
-    // Cameras
-    commands.spawn(Camera2dBundle::default());
-
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
     // Debug
     // commands.spawn(PerfUiCompleteBundle::default());
 
     // Text
     let font_bold = asset_server.load("fonts/FiraSans-Bold.ttf");
     let font_medium = asset_server.load("fonts/FiraMono-Medium.ttf");
+    let text_color = theme.palette().text;
 
     commands.spawn((
         LeftBoard,
@@ -171,7 +281,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         style: TextStyle {
                             font: font_bold.clone(),
                             font_size: 30.0,
-                            color: Color::BLACK,
+                            color: text_color,
                         },
                     },
                     TextSection {
@@ -179,7 +289,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         style: TextStyle {
                             font: font_medium.clone(),
                             font_size: 30.0,
-                            color: Color::BLACK,
+                            color: text_color,
                         },
                     },
                     TextSection {
@@ -187,7 +297,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         style: TextStyle {
                             font: font_bold.clone(),
                             font_size: 30.0,
-                            color: Color::BLACK,
+                            color: text_color,
                         },
                     },
                     TextSection {
@@ -195,7 +305,55 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         style: TextStyle {
                             font: font_medium.clone(),
                             font_size: 30.0,
-                            color: Color::BLACK,
+                            color: text_color,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 30.0,
+                            color: text_color,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: font_medium.clone(),
+                            font_size: 30.0,
+                            color: text_color,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 30.0,
+                            color: text_color,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: font_medium.clone(),
+                            font_size: 30.0,
+                            color: text_color,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: font_bold.clone(),
+                            font_size: 30.0,
+                            color: text_color,
+                        },
+                    },
+                    TextSection {
+                        value: "".to_string(),
+                        style: TextStyle {
+                            font: font_medium.clone(),
+                            font_size: 30.0,
+                            color: text_color,
                         },
                     },
                     TextSection {
@@ -203,7 +361,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         style: TextStyle {
                             font: font_bold,
                             font_size: 30.0,
-                            color: Color::BLACK,
+                            color: text_color,
                         },
                     },
                     TextSection {
@@ -211,7 +369,7 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
                         style: TextStyle {
                             font: font_medium,
                             font_size: 30.0,
-                            color: Color::BLACK,
+                            color: text_color,
                         },
                     },
                 ],
@@ -228,64 +386,71 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     ));
 }
 
-fn setup_arena(mut commands: Commands, asset_server: Res<AssetServer>) {
-    // Random
-    let mut rng = thread_rng();
+fn setup_arena(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level: Res<Level>,
+    rules: Res<MatchRules>,
+    theme: Res<Theme>,
+) {
+    info!("building level [{}]", level.name);
 
     // Buildings
-    let colors = vec![
-        Color::rgb_u8(174, 177, 166),
-        Color::rgb_u8(98, 88, 81),
-        Color::rgb_u8(208, 208, 181),
-    ];
-    let num_buildings = (SCREEN_WIDTH / BUILDING_WIDTH).round() as i8;
-    assert_eq!(num_buildings as f32 * BUILDING_WIDTH, SCREEN_WIDTH);
-
-    let start_left = -SCREEN_WIDTH / 2.0;
-    let start_bottom = -SCREEN_HEIGHT / 2.0;
-    for i in 0..num_buildings {
-        let n = i as f32;
-        let height = rng.next_u32() as f32 % (SCREEN_HEIGHT / 2.0) + SCREEN_HEIGHT / 8.0;
-        let height = f32::round(height / BUILDING_BRICK_HEIGHT) * BUILDING_BRICK_HEIGHT;
-        let color = *colors.choose(&mut rng).unwrap_or(&Color::BLACK);
-        let x = start_left + BUILDING_WIDTH / 2.0 + (BUILDING_WIDTH * n);
+    let mut rng = StdRng::seed_from_u64(rules.seed);
+    let start_left = -level.width / 2.0;
+    let start_bottom = -level.height / 2.0;
+    let lit_window_chance = theme.palette().lit_window_chance;
+    for (i, b) in level.buildings.iter().enumerate() {
         spawn_building(
             format!("b{}", i),
             &mut commands,
-            color,
-            BUILDING_WIDTH,
-            height,
-            x,
-            start_bottom + (height / 2.0),
+            b,
+            start_left + b.x + b.width / 2.0,
+            start_bottom + (b.height / 2.0),
+            &mut rng,
+            lit_window_chance,
         );
+    }
 
-        if i == 0 || i == num_buildings - 1 {
-            let g = if i == 0 {
-                Gorilla::one("Player 1".to_string())
-            } else {
-                Gorilla::two("Player 2".to_string())
-            };
-
-            let gorilla_y = start_bottom + height + GORILLA_HEIGHT / 2.0;
-            commands.spawn((
-                g,
-                SpriteBundle {
-                    transform: Transform {
-                        translation: Vec2::new(x, gorilla_y).extend(GORILLA_Z_INDEX),
-                        scale: Vec2::new(GORILLA_WIDTH, GORILLA_HEIGHT).extend(1.0),
-                        ..default()
-                    },
-                    texture: asset_server.load("sprites/gorilla.png"),
-                    sprite: Sprite {
-                        custom_size: Some(Vec2::new(1.0, 1.0)),
-                        ..default()
-                    },
+    // Gorillas
+    for (g, x) in [
+        (
+            Gorilla::one("Player 1".to_string()),
+            level.spawns.player_one,
+        ),
+        (
+            Gorilla::two("Player 2".to_string()),
+            level.spawns.player_two,
+        ),
+    ] {
+        let stride = Stride::new(&g.player);
+        let ammo = rules.ammo.clone();
+        let mut weapon = Weapon::default();
+        weapon.keep_in_stock(&ammo);
+        let height = level.roof_height(x).unwrap_or(0.0);
+        let gorilla_y = start_bottom + height + GORILLA_HEIGHT / 2.0;
+        commands.spawn((
+            g,
+            SpriteBundle {
+                transform: Transform {
+                    translation: Vec2::new(start_left + x, gorilla_y).extend(GORILLA_Z_INDEX),
+                    scale: Vec2::new(GORILLA_WIDTH, GORILLA_HEIGHT).extend(1.0),
                     ..default()
                 },
-                AngleSpeed::default(),
-                Collider,
-            ));
-        }
+                texture: asset_server.load("sprites/gorilla.png"),
+                sprite: Sprite {
+                    custom_size: Some(Vec2::new(1.0, 1.0)),
+                    ..default()
+                },
+                ..default()
+            },
+            AngleSpeed::default(),
+            weapon,
+            ammo,
+            PowerUps::default(),
+            stride,
+            Collider,
+        ));
     }
 }
 
@@ -295,18 +460,27 @@ fn state_logger(mut action_change: EventReader<StateTransitionEvent<Action>>) {
     }
 }
 
-fn spawn_building(
+pub(crate) fn spawn_building(
     name: String,
     commands: &mut Commands,
-    color: Color,
-    width: f32,
-    height: f32,
+    building: &BuildingDef,
     x: f32,
     y: f32,
+    rng: &mut StdRng,
+    lit_window_chance: f64,
 ) {
+    let width = building.width;
+    let height = building.height;
+    let color = building.color.into();
     let num_bricks_width = f32::round(width / BUILDING_BRICK_WIDTH) as usize;
     let num_bricks_height = f32::round(height / BUILDING_BRICK_HEIGHT) as usize;
     debug!("spawning [{name}] ... {width}x{height} bricks {num_bricks_width}x{num_bricks_height} @ center={x},{y}");
+
+    // one entry per window cell, so both bricks of a window are lit together
+    let lit_windows: Vec<bool> = (0..num_bricks_width * (num_bricks_height / WINDOW_ROWS + 1))
+        .map(|_| rng.gen_bool(lit_window_chance))
+        .collect();
+
     for r in 0..num_bricks_height {
         for c in 0..num_bricks_width {
             let bx = x - (width / 2.0)
@@ -316,15 +490,34 @@ fn spawn_building(
                 + (BUILDING_BRICK_HEIGHT / 2.0)
                 + (r as f32 * BUILDING_BRICK_HEIGHT);
             debug!("spawning brick for [{name}] ... center={bx},{by}");
+            let is_window =
+                building.windows && is_window_cell(r, c, num_bricks_width, num_bricks_height);
+            let material = if building.awning && r + 1 == num_bricks_height {
+                BrickMaterial::Awning
+            } else {
+                building.material
+            };
+            let brick_color = if !is_window {
+                material
+                    .color()
+                    .unwrap_or_else(|| building.bricks.color(color, r, c))
+            } else if lit_windows[(r / WINDOW_ROWS) * num_bricks_width + c] {
+                WINDOW_LIT_COLOR
+            } else {
+                WINDOW_DARK_COLOR
+            };
             commands.spawn((
-                BuildingBrick,
+                BuildingBrick::new(material),
                 SpriteBundle {
                     transform: Transform {
                         translation: Vec2::new(bx, by).extend(BUILDING_Z_INDEX),
                         scale: Vec2::new(BUILDING_BRICK_WIDTH, BUILDING_BRICK_HEIGHT).extend(1.0), // scale z=1.0 in 2D
                         ..default()
                     },
-                    sprite: Sprite { color, ..default() },
+                    sprite: Sprite {
+                        color: brick_color,
+                        ..default()
+                    },
                     ..default()
                 },
                 Collider,
@@ -333,15 +526,52 @@ fn spawn_building(
     }
 }
 
+/// Windows are every other column and two rows out of every three, leaving a solid
+/// wall around the edges and a solid roof
+fn is_window_cell(row: usize, column: usize, num_columns: usize, num_rows: usize) -> bool {
+    // the first row of each cell is the wall between windows
+    let row_in_cell = row % WINDOW_ROWS;
+    let window_top = row - row_in_cell + WINDOW_ROWS - 1;
+    column % 2 == 1 && column + 1 < num_columns && row_in_cell > 0 && window_top + 1 < num_rows
+}
+
 fn winner_player_system(
     mut next_action: ResMut<NextState<Action>>,
     mut next_player: ResMut<NextState<Player>>,
     mut gorilla_collision_event: EventReader<GorillaCollisionEvent>,
+    mut gorilla_fell_event: EventReader<GorillaFellEvent>,
+    mut shield_query: Query<(&Gorilla, &mut Shield)>,
 ) {
+    let mut down = Vec::new();
+    // shields do not help when falling
+    for event in gorilla_fell_event.read() {
+        info!("saw {:?} fall", &event.player);
+        down.push(event.player.clone());
+    }
     for event in gorilla_collision_event.read() {
         info!("saw gorilla collision on {:?}", &event.player);
+        if let Some((_, mut shield)) = shield_query
+            .iter_mut()
+            .find(|(g, _)| g.player == event.player)
+        {
+            // holds until the explosion it stopped is over
+            if shield.stops(event.explosion) {
+                if shield.absorbing.is_none() {
+                    info!("{:?} is shielded", &event.player);
+                    shield.absorbing = Some(event.explosion);
+                }
+                continue;
+            }
+        }
+        down.push(event.player.clone());
+    }
+    // bananas thrown at the same time can take out both gorillas in the same step
+    if down.contains(&Player::One) && down.contains(&Player::Two) {
+        info!("both gorillas are down, it is a draw");
+        next_action.set(Action::Draw);
+    } else if let Some(loser) = down.first() {
         // set the winner to the other player
-        next_player.set(match event.player {
+        next_player.set(match loser {
             Player::One => Player::Two,
             Player::Two => Player::One,
         });
@@ -349,24 +579,87 @@ fn winner_player_system(
     }
 }
 
+/// nobody was knocked out or fell out of the arena this frame
+fn no_gorilla_down(
+    mut collision_events: EventReader<GorillaCollisionEvent>,
+    mut fell_events: EventReader<GorillaFellEvent>,
+    shield_query: Query<(&Gorilla, &Shield)>,
+) -> bool {
+    // read them all, leaving some for the next frame would see them twice
+    let hit = collision_events
+        .read()
+        .filter(|e| {
+            !shield_query
+                .iter()
+                .any(|(g, shield)| g.player == e.player && shield.stops(e.explosion))
+        })
+        .count();
+    let fell = fell_events.read().count();
+    hit == 0 && fell == 0
+}
+
 fn next_player_system(
     action: Res<State<Action>>,
     mut next_action: ResMut<NextState<Action>>,
     player: Res<State<Player>>,
     mut next_player: ResMut<NextState<Player>>,
-    banana_collision_event: EventReader<BananaCollisionEvent>,
-    banana_gone_event: EventReader<BananaGoneEvent>,
+    projectiles: Res<TurnProjectiles>,
+    turn_spent_event: EventReader<TurnSpentEvent>,
+    rules: Res<MatchRules>,
+    mut power_ups_query: Query<(&Gorilla, &mut PowerUps)>,
 ) {
-    if !banana_collision_event.is_empty() || !banana_gone_event.is_empty() {
-        info!(
-            "next player, current is {:?}, action is {:?}",
-            player, action
-        );
+    if !projectiles.all_resolved() && turn_spent_event.is_empty() {
+        return;
+    }
+    if rules.simultaneous {
+        // everyone throws every time, so there is nobody to hand over to
+        info!("everything landed, plan the next throws");
+        next_player.set(Player::One);
+        next_action.set(Action::Planning);
+    } else {
+        let extra_shot = power_ups_query
+            .iter_mut()
+            .find(|(g, _)| &g.player == player.get())
+            .is_some_and(|(_, mut power_ups)| power_ups.take_extra_shot());
+        if extra_shot {
+            info!("{:?} gets an extra shot", player.get());
+        } else {
+            info!(
+                "next player, current is {:?}, action is {:?}",
+                player, action
+            );
+            next_player.set(match player.get() {
+                Player::One => Player::Two,
+                Player::Two => Player::One,
+            });
+        }
+        next_action.set(Action::Enter);
+    }
+}
+
+/// gorillas with nothing left to throw miss their turn, when nobody has anything left
+/// it is a draw
+fn out_of_ammo_system(
+    mut next_action: ResMut<NextState<Action>>,
+    player: Res<State<Player>>,
+    mut next_player: ResMut<NextState<Player>>,
+    query: Query<(&Gorilla, &Ammo)>,
+) {
+    let empty_handed = query
+        .iter()
+        .any(|(g, ammo)| &g.player == player.get() && ammo.is_empty());
+    if !empty_handed {
+        return;
+    }
+    if query.iter().all(|(_, ammo)| ammo.is_empty()) {
+        info!("everyone is out of ammo, it is a draw");
+        next_action.set(Action::Draw);
+    } else {
+        info!("{:?} is out of ammo, skipping their turn", player.get());
         next_player.set(match player.get() {
             Player::One => Player::Two,
             Player::Two => Player::One,
         });
-        next_action.set(Action::Enter);
     }
 }
 
@@ -432,76 +725,153 @@ impl Default for MoveArrowState {
     }
 }
 
+const AIM_ACTIONS: [GameAction; 4] = [
+    GameAction::AimUp,
+    GameAction::AimDown,
+    GameAction::PowerUp,
+    GameAction::PowerDown,
+];
+
 fn rotate_and_change_velocity_input(
     time: Res<Time>,
+    action: Res<State<Action>>,
     player: Res<State<Player>>,
     mut query_angle_speed: Query<(&Gorilla, &mut AngleSpeed)>,
     mut move_arrow_state: Local<MoveArrowState>,
-    keyboard_input: Res<ButtonInput<KeyCode>>,
+    actions: Res<PlayerActions>,
+    mut sounds: EventWriter<PlaySoundEvent>,
 ) {
+    let input = actions.get(player.get());
+    // everyone can hear the ticks, counting them would give away a secret plan
+    let tick = action.get() != &Action::Planning;
     for (ref mut g, ref mut a) in query_angle_speed.iter_mut() {
         if player.get() == &g.player {
-            if keyboard_input.any_just_pressed([
-                KeyCode::ArrowUp,
-                KeyCode::ArrowDown,
-                KeyCode::ArrowLeft,
-                KeyCode::ArrowRight,
-            ]) {
+            if input.any_just_pressed(AIM_ACTIONS) {
                 move_arrow_state.timer.reset();
-                mutate_speed_angle(&keyboard_input, a);
+                mutate_speed_angle(input, a);
+                if tick {
+                    sounds.send(PlaySoundEvent(Sound::AimTick));
+                }
             }
-            if keyboard_input.any_pressed([
-                KeyCode::ArrowUp,
-                KeyCode::ArrowDown,
-                KeyCode::ArrowLeft,
-                KeyCode::ArrowRight,
-            ]) {
+            if input.any_pressed(AIM_ACTIONS) {
                 move_arrow_state.timer.tick(time.delta());
                 if move_arrow_state.timer.finished() {
-                    mutate_speed_angle(&keyboard_input, a);
+                    mutate_speed_angle(input, a);
+                    if tick {
+                        sounds.send(PlaySoundEvent(Sound::AimTick));
+                    }
                 }
             }
         }
     }
 }
 
-fn mutate_speed_angle(keyboard_input: &Res<ButtonInput<KeyCode>>, a: &mut AngleSpeed) {
-    if keyboard_input.pressed(KeyCode::ArrowUp) {
-        a.angle = cmp::min(230, a.angle + 1);
+fn mutate_speed_angle(input: &ButtonInput<GameAction>, a: &mut AngleSpeed) {
+    if input.pressed(GameAction::AimUp) {
+        a.angle = cmp::min(AngleSpeed::MAX_ANGLE, a.angle + 1);
     }
-    if keyboard_input.pressed(KeyCode::ArrowDown) {
+    if input.pressed(GameAction::AimDown) {
         a.angle = cmp::max(0, a.angle as i16 - 1) as u8;
     }
-    if keyboard_input.pressed(KeyCode::ArrowRight) {
-        a.speed = cmp::min(200, a.speed + 1);
+    if input.pressed(GameAction::PowerUp) {
+        a.speed = cmp::min(AngleSpeed::MAX_SPEED, a.speed + 1);
     }
-    if keyboard_input.pressed(KeyCode::ArrowLeft) {
-        a.speed = cmp::max(10, a.speed - 1);
+    if input.pressed(GameAction::PowerDown) {
+        a.speed = cmp::max(AngleSpeed::MIN_SPEED, a.speed - 1);
     }
 }
 
 fn update_text_left(
     action: Res<State<Action>>,
+    turn_action: Res<State<TurnAction>>,
     player: Res<State<Player>>,
+    controls: Res<Controls>,
+    typed_aim: Res<TypedAim>,
+    actions: Res<PlayerActions>,
+    clock: Res<TurnClock>,
     mut query: Query<&mut Text, With<LeftBoard>>,
-    name_query: Query<(&Gorilla, &AngleSpeed)>,
+    name_query: Query<(&Gorilla, &AngleSpeed, &Weapon, &Ammo)>,
 ) {
     let mut text = query.single_mut();
-    if let Some((g, a)) = name_query.iter().find(|(g, _)| &g.player == player.get()) {
+    if let Some((g, a, weapon, ammo)) = name_query
+        .iter()
+        .find(|(g, _, _, _)| &g.player == player.get())
+    {
         text.sections[1].value = g.name.to_string();
 
-        let (action, v) = match action.get() {
-            Action::Enter => (
+        // a plan made in secret only shows while its player peeks
+        let hidden = action.get() == &Action::Planning
+            && !actions.get(player.get()).pressed(GameAction::Peek);
+        let conceal = |value: String| if hidden { "???".to_string() } else { value };
+
+        let (label, name) = match action.get() {
+            Action::Enter | Action::Planning if turn_action.get() == &TurnAction::Throw => {
+                match ammo.left(weapon.0) {
+                    Some(left) => (
+                        "\nWeapon: ",
+                        conceal(format!("{} ({left} left)", weapon.def().name)),
+                    ),
+                    None => ("\nWeapon: ", conceal(weapon.def().name.to_string())),
+                }
+            }
+            _ => ("", "".to_string()),
+        };
+        text.sections[8].value = label.to_string();
+        text.sections[9].value = name;
+
+        let (label, left) = match (action.get(), clock.seconds_left()) {
+            (Action::Enter | Action::Planning, Some(secs)) => ("\nTime left: ", format!("{secs}s")),
+            _ => ("", "".to_string()),
+        };
+        text.sections[10].value = label.to_string();
+        text.sections[11].value = left;
+
+        let no_value = || ("", "".to_string());
+        let aim = || match controls.aim_mode(player.get()) {
+            AimMode::Arrows => (
                 "How do you want to throw?",
-                ("\nVelocity: ", format!("{}(m/s) @ {}°", a.speed, a.angle)),
+                (
+                    "\nVelocity: ",
+                    conceal(format!("{}(m/s) @ {}°", a.speed, a.angle)),
+                ),
+                no_value(),
+            ),
+            AimMode::Typed => (
+                "Type your throw",
+                (
+                    "\nAngle: ",
+                    conceal(typed_aim.field_text(TypedField::Angle)),
+                ),
+                (
+                    "\nVelocity: ",
+                    conceal(typed_aim.field_text(TypedField::Speed)),
+                ),
             ),
-            Action::Throwing => ("Chunk", ("", "".to_string())),
-            Action::Watching => ("Whoa!", ("", "".to_string())),
-            Action::Winner => ("Winner !!!", ("", "".to_string())),
+        };
+        let (action, v, w) = match action.get() {
+            Action::Enter => match turn_action.get() {
+                TurnAction::Shield => (
+                    "Raise a shield instead of throwing?",
+                    no_value(),
+                    no_value(),
+                ),
+                TurnAction::Wall => ("Build a wall instead of throwing?", no_value(), no_value()),
+                TurnAction::Throw => aim(),
+            },
+            Action::Planning => {
+                let (_, v, w) = aim();
+                ("Plan your throw in secret", v, w)
+            }
+            Action::Throwing => ("Chunk", no_value(), no_value()),
+            Action::Watching => ("Whoa!", no_value(), no_value()),
+            Action::Winner => ("Winner !!!", no_value(), no_value()),
+            Action::Draw => ("It's a draw", no_value(), no_value()),
         };
         text.sections[3].value = action.to_string();
         text.sections[4].value = v.0.to_string();
         text.sections[5].value = v.1;
+        text.sections[6].value = w.0.to_string();
+        text.sections[7].value = w.1;
     } else {
         error!("unable to find gorilla for player {:?}", player.get());
     }
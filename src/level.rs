@@ -0,0 +1,408 @@
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::*;
+use crate::skyline::SkylineStyle;
+use crate::theme::Theme;
+
+/// An rgb color, `(r, g, b)` in level files
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub(crate) struct Rgb(pub(crate) u8, pub(crate) u8, pub(crate) u8);
+
+impl From<Rgb> for Color {
+    fn from(rgb: Rgb) -> Self {
+        Color::rgb_u8(rgb.0, rgb.1, rgb.2)
+    }
+}
+
+/// How the bricks of a building are colored
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub(crate) enum BrickPattern {
+    /// every brick is the building color
+    #[default]
+    Solid,
+    /// every other row of bricks is this color
+    Stripes(Rgb),
+    /// every other brick is this color
+    Checker(Rgb),
+}
+
+impl BrickPattern {
+    pub(crate) fn color(&self, building: Color, row: usize, column: usize) -> Color {
+        match self {
+            BrickPattern::Solid => building,
+            BrickPattern::Stripes(other) if row % 2 == 1 => (*other).into(),
+            BrickPattern::Checker(other) if (row + column) % 2 == 1 => (*other).into(),
+            _ => building,
+        }
+    }
+}
+
+/// What the bricks of a building are made of, which decides how they take a hit
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum BrickMaterial {
+    /// gone after one hit, like the original game
+    #[default]
+    Brick,
+    /// takes a few hits from different throws before it breaks
+    Concrete,
+    /// shatters from any blast nearby, even one that does not touch it
+    Glass,
+    /// never breaks, bananas still blow up on it
+    Steel,
+    /// bananas bounce off the top of it, blasts still break it
+    Awning,
+}
+
+impl BrickMaterial {
+    /// how many throws it takes to break, `None` if nothing can
+    pub(crate) fn hits(&self) -> Option<u8> {
+        match self {
+            BrickMaterial::Concrete => Some(CONCRETE_HITS),
+            BrickMaterial::Steel => None,
+            _ => Some(1),
+        }
+    }
+
+    /// the color of the material, `None` to use the building color and pattern
+    pub(crate) fn color(&self) -> Option<Color> {
+        match self {
+            BrickMaterial::Brick => None,
+            BrickMaterial::Concrete => Some(CONCRETE_COLOR),
+            BrickMaterial::Glass => Some(GLASS_COLOR),
+            BrickMaterial::Steel => Some(STEEL_COLOR),
+            BrickMaterial::Awning => Some(AWNING_COLOR),
+        }
+    }
+
+    /// how fast the debris flies compared to a plain brick
+    pub(crate) fn debris_speed(&self) -> f32 {
+        match self {
+            BrickMaterial::Concrete => 0.5,
+            BrickMaterial::Glass => 2.0,
+            _ => 1.0,
+        }
+    }
+
+    /// how fast the debris fades away compared to a plain brick
+    pub(crate) fn debris_fade(&self) -> f32 {
+        match self {
+            BrickMaterial::Glass => 3.0,
+            _ => 1.0,
+        }
+    }
+}
+
+/// One building, measured in pixels from the bottom left corner of the arena
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct BuildingDef {
+    pub(crate) x: f32,
+    pub(crate) width: f32,
+    pub(crate) height: f32,
+    pub(crate) color: Rgb,
+    #[serde(default)]
+    pub(crate) bricks: BrickPattern,
+    #[serde(default = "has_windows")]
+    pub(crate) windows: bool,
+    #[serde(default)]
+    pub(crate) material: BrickMaterial,
+    /// the top row of bricks is a bouncy awning
+    #[serde(default)]
+    pub(crate) awning: bool,
+}
+
+fn has_windows() -> bool {
+    true
+}
+
+impl BuildingDef {
+    fn right(&self) -> f32 {
+        self.x + self.width
+    }
+}
+
+/// Where each gorilla stands, as an x position on top of a building
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Spawns {
+    pub(crate) player_one: f32,
+    pub(crate) player_two: f32,
+}
+
+/// Everything needed to build an arena, either loaded from a file or generated
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Level {
+    /// where the level came from, only used for logging
+    #[serde(skip)]
+    pub(crate) name: String,
+    /// size of the world in pixels, the camera scales it to fit the window
+    #[serde(default = "arena_width")]
+    pub(crate) width: f32,
+    #[serde(default = "arena_height")]
+    pub(crate) height: f32,
+    /// in m/s², it is scaled to pixels the same way the throw speed is
+    #[serde(default = "earth_gravity")]
+    pub(crate) gravity: f32,
+    #[serde(default)]
+    pub(crate) wind: i32,
+    pub(crate) buildings: Vec<BuildingDef>,
+    pub(crate) spawns: Spawns,
+    /// how the level looks unless the command line says otherwise
+    #[serde(default)]
+    pub(crate) theme: Option<Theme>,
+}
+
+fn arena_width() -> f32 {
+    ARENA_WIDTH
+}
+
+fn arena_height() -> f32 {
+    ARENA_HEIGHT
+}
+
+fn earth_gravity() -> f32 {
+    -GRAVITY_Y_ACCEL / PIXEL_STEP_SIZE
+}
+
+#[derive(Debug)]
+pub(crate) enum LevelError {
+    Read(PathBuf, std::io::Error),
+    Parse(PathBuf, ron::error::SpannedError),
+    ArenaSize(f32, f32),
+    Gravity(f32),
+    Wind(i32),
+    NoBuildings,
+    BadSize(usize),
+    OutOfBounds(usize),
+    Overlapping(usize, usize),
+    NoRoof(Player, f32),
+}
+
+impl fmt::Display for LevelError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LevelError::Read(path, e) => write!(f, "unable to read level {}: {e}", path.display()),
+            LevelError::Parse(path, e) => write!(f, "malformed level {}: {e}", path.display()),
+            LevelError::ArenaSize(w, h) => write!(
+                f,
+                "the arena must be a positive multiple of {BUILDING_BRICK_WIDTH}x{BUILDING_BRICK_HEIGHT} bricks, got {w}x{h}"
+            ),
+            LevelError::Gravity(g) => write!(f, "gravity must be more than 0, got {g}"),
+            LevelError::Wind(w) => write!(
+                f,
+                "wind must be between -{MAX_WIND} and {MAX_WIND}, got {w}"
+            ),
+            LevelError::NoBuildings => write!(f, "a level needs at least one building"),
+            LevelError::BadSize(i) => write!(
+                f,
+                "building {i} must be a positive multiple of {BUILDING_BRICK_WIDTH}x{BUILDING_BRICK_HEIGHT} bricks"
+            ),
+            LevelError::OutOfBounds(i) => write!(f, "building {i} does not fit in the arena"),
+            LevelError::Overlapping(a, b) => write!(f, "buildings {a} and {b} overlap"),
+            LevelError::NoRoof(player, x) => {
+                write!(f, "{player:?} spawns at x={x} which is not on a building")
+            }
+        }
+    }
+}
+
+impl std::error::Error for LevelError {}
+
+impl Level {
+    pub(crate) fn load(path: &Path) -> Result<Level, LevelError> {
+        let contents =
+            fs::read_to_string(path).map_err(|e| LevelError::Read(path.to_path_buf(), e))?;
+        let mut level: Level =
+            ron::from_str(&contents).map_err(|e| LevelError::Parse(path.to_path_buf(), e))?;
+        level.validate()?;
+        level.name = path.display().to_string();
+        Ok(level)
+    }
+
+    /// a random city in the given style filling a `width` by `height` arena,
+    /// the same seed always builds the same city. Without a theme one is picked
+    /// to go with the wind.
+    pub(crate) fn generate(
+        style: SkylineStyle,
+        theme: Option<Theme>,
+        seed: u64,
+        width: f32,
+        height: f32,
+    ) -> Level {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let wind = random_wind(&mut rng);
+        let theme = theme.unwrap_or_else(|| Theme::for_wind(wind, seed));
+        let colors = theme.palette().buildings;
+        let buildings = style
+            .generator()
+            .buildings(&mut rng, width, height, &colors);
+        let spawns = Spawns {
+            player_one: buildings.first().map_or(0.0, |b| b.x + b.width / 2.0),
+            player_two: buildings.last().map_or(0.0, |b| b.x + b.width / 2.0),
+        };
+        Level {
+            name: format!("{style:?} skyline with seed {seed}"),
+            width,
+            height,
+            gravity: earth_gravity(),
+            wind,
+            buildings,
+            spawns,
+            theme: Some(theme),
+        }
+    }
+
+    fn validate(&self) -> Result<(), LevelError> {
+        if !whole_bricks(self.width, BUILDING_BRICK_WIDTH)
+            || !whole_bricks(self.height, BUILDING_BRICK_HEIGHT)
+        {
+            return Err(LevelError::ArenaSize(self.width, self.height));
+        }
+        // NaN and infinity would send the banana nowhere
+        if !(self.gravity.is_finite() && self.gravity > 0.0) {
+            return Err(LevelError::Gravity(self.gravity));
+        }
+        if self.wind.abs() > MAX_WIND {
+            return Err(LevelError::Wind(self.wind));
+        }
+        if self.buildings.is_empty() {
+            return Err(LevelError::NoBuildings);
+        }
+        for (i, b) in self.buildings.iter().enumerate() {
+            if !whole_bricks(b.width, BUILDING_BRICK_WIDTH)
+                || !whole_bricks(b.height, BUILDING_BRICK_HEIGHT)
+            {
+                return Err(LevelError::BadSize(i));
+            }
+            if b.x < 0.0 || b.right() > self.width || b.height > self.height {
+                return Err(LevelError::OutOfBounds(i));
+            }
+            for (j, other) in self.buildings.iter().enumerate().skip(i + 1) {
+                if b.x < other.right() && other.x < b.right() {
+                    return Err(LevelError::Overlapping(i, j));
+                }
+            }
+        }
+        for (player, x) in [
+            (Player::One, self.spawns.player_one),
+            (Player::Two, self.spawns.player_two),
+        ] {
+            if self.roof_height(x).is_none() {
+                return Err(LevelError::NoRoof(player, x));
+            }
+        }
+        Ok(())
+    }
+
+    /// gravity as a world acceleration
+    pub(crate) fn gravity_acceleration(&self) -> Vec2 {
+        Vec2::new(0.0, -self.gravity * PIXEL_STEP_SIZE)
+    }
+
+    /// height of the building at `x`, if there is one
+    pub(crate) fn roof_height(&self, x: f32) -> Option<f32> {
+        self.buildings
+            .iter()
+            .find(|b| b.x <= x && x < b.right())
+            .map(|b| b.height)
+    }
+}
+
+fn whole_bricks(size: f32, brick: f32) -> bool {
+    size > 0.0 && size % brick == 0.0
+}
+
+pub(crate) fn random_wind(rng: &mut impl RngCore) -> i32 {
+    (rng.next_u32() % (MAX_WIND as u32 * 2)) as i32 - MAX_WIND
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn building(x: f32, bricks_wide: f32, bricks_high: f32) -> BuildingDef {
+        BuildingDef {
+            x,
+            width: bricks_wide * BUILDING_BRICK_WIDTH,
+            height: bricks_high * BUILDING_BRICK_HEIGHT,
+            color: Rgb(0, 170, 170),
+            bricks: BrickPattern::default(),
+            windows: true,
+            material: BrickMaterial::default(),
+            awning: false,
+        }
+    }
+
+    /// two buildings side by side with a gorilla on each
+    fn level() -> Level {
+        let left = building(0.0, 10.0, 20.0);
+        let right = building(left.right(), 10.0, 30.0);
+        Level {
+            name: "test".to_string(),
+            width: ARENA_WIDTH,
+            height: ARENA_HEIGHT,
+            gravity: earth_gravity(),
+            wind: 0,
+            spawns: Spawns {
+                player_one: left.x + left.width / 2.0,
+                player_two: right.x + right.width / 2.0,
+            },
+            buildings: vec![left, right],
+            theme: None,
+        }
+    }
+
+    #[test]
+    fn valid_levels_pass() {
+        assert!(level().validate().is_ok());
+        let canyon: Level = ron::from_str(include_str!("../assets/levels/canyon.ron")).unwrap();
+        assert!(canyon.validate().is_ok());
+    }
+
+    #[test]
+    fn overlapping_buildings_are_rejected() {
+        let mut level = level();
+        level.buildings[1].x -= BUILDING_BRICK_WIDTH;
+        assert!(matches!(
+            level.validate(),
+            Err(LevelError::Overlapping(0, 1))
+        ));
+    }
+
+    #[test]
+    fn partial_bricks_are_rejected() {
+        let mut level = level();
+        level.buildings[1].width += 1.0;
+        assert!(matches!(level.validate(), Err(LevelError::BadSize(1))));
+
+        let mut level = self::level();
+        level.buildings[0].height = 0.0;
+        assert!(matches!(level.validate(), Err(LevelError::BadSize(0))));
+    }
+
+    #[test]
+    fn spawns_off_the_roofs_are_rejected() {
+        let mut level = level();
+        level.spawns.player_two = level.width - 1.0;
+        assert!(matches!(
+            level.validate(),
+            Err(LevelError::NoRoof(Player::Two, _))
+        ));
+    }
+
+    #[test]
+    fn bad_gravity_is_rejected() {
+        for gravity in [0.0, -9.8, f32::NAN, f32::INFINITY] {
+            let mut level = level();
+            level.gravity = gravity;
+            assert!(
+                matches!(level.validate(), Err(LevelError::Gravity(_))),
+                "gravity {gravity} was allowed"
+            );
+        }
+    }
+}
@@ -1,16 +1,66 @@
 use bevy::prelude::*;
 
+mod aim;
 mod arrow;
 mod audio;
 mod banana;
+mod camera;
 mod collision;
 mod constants;
+mod controls;
+mod defense;
 mod game;
+mod level;
+mod movement;
+mod obstacles;
 mod physics;
+mod pickups;
+mod planning;
 mod players;
 mod prelude;
+mod projectile;
+mod rules;
+mod skyline;
+mod sun;
+mod theme;
+mod trajectory;
+mod turn_timer;
+mod weather;
 mod wind;
 
 fn main() {
-    App::new().add_plugins(game::GamePlugin).run();
+    let rules = match rules::MatchRules::from_args(std::env::args().skip(1)) {
+        Ok(rules) => rules,
+        Err(e) => {
+            eprintln!("{e}");
+            std::process::exit(2);
+        }
+    };
+    let level = match &rules.level {
+        Some(path) => match level::Level::load(path) {
+            Ok(level) => level,
+            Err(e) => {
+                eprintln!("{e}");
+                std::process::exit(2);
+            }
+        },
+        None => level::Level::generate(
+            rules.skyline,
+            rules.theme,
+            rules.seed,
+            rules.arena_width,
+            rules.arena_height,
+        ),
+    };
+    // the command line wins over the level, otherwise it goes with the wind
+    let theme = rules
+        .theme
+        .or(level.theme)
+        .unwrap_or_else(|| theme::Theme::for_wind(level.wind, rules.seed));
+    App::new()
+        .insert_resource(rules)
+        .insert_resource(level)
+        .insert_resource(theme)
+        .add_plugins(game::GamePlugin)
+        .run();
 }
@@ -0,0 +1,401 @@
+use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+
+use crate::controls::{action_just_pressed, GameAction, PlayerActions};
+use crate::game::{Action, BuildingBrick, InGameplaySet};
+use crate::level::Level;
+use crate::prelude::*;
+use crate::turn_timer::turn_time_left;
+
+// how close feet have to be to the top of a brick to stand on it
+const FOOTING: f32 = 1.0;
+
+/// How far a gorilla can still get this turn
+#[derive(Component)]
+pub(crate) struct Stride {
+    /// pixels left to walk
+    left: f32,
+    jumped: bool,
+    /// the way they last walked, jumps go that way too
+    facing: f32,
+}
+
+impl Stride {
+    pub(crate) fn new(player: &Player) -> Self {
+        Stride {
+            left: GORILLA_WALK_DISTANCE,
+            jumped: false,
+            // start out facing the other gorilla
+            facing: match player {
+                Player::One => 1.0,
+                Player::Two => -1.0,
+            },
+        }
+    }
+}
+
+/// A gorilla in the air after jumping or walking off an edge, the physics moves it
+/// until it lands on a brick
+#[derive(Component)]
+pub(crate) struct Airborne;
+
+/// A gorilla fell out of the arena, which is as bad as being hit
+#[derive(Event, Debug)]
+pub(crate) struct GorillaFellEvent {
+    pub(crate) player: Player,
+}
+
+// never a gorilla, so the gorilla being moved can be borrowed at the same time
+type Bricks<'w, 's> =
+    Query<'w, 's, &'static Transform, (With<BuildingBrick>, With<Collider>, Without<Gorilla>)>;
+
+pub(crate) struct MovementPlugin;
+impl Plugin for MovementPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<GorillaFellEvent>()
+            .add_systems(OnEnter(Action::Enter), reset_strides)
+            .add_systems(
+                Update,
+                (walk, jump.run_if(action_just_pressed(GameAction::Jump)))
+                    .run_if(in_state(Action::Enter))
+                    .run_if(turn_time_left)
+                    .in_set(InGameplaySet::Gorillas),
+            )
+            .add_systems(FixedUpdate, land.after(InGameplaySet::Movement));
+    }
+}
+
+pub(crate) fn no_one_airborne(query: Query<(), With<Airborne>>) -> bool {
+    query.is_empty()
+}
+
+fn reset_strides(mut commands: Commands, query: Query<(Entity, &Gorilla)>) {
+    for (e, g) in query.iter() {
+        commands.entity(e).insert(Stride::new(&g.player));
+    }
+}
+
+fn walk(
+    mut commands: Commands,
+    time: Res<Time>,
+    player: Res<State<Player>>,
+    actions: Res<PlayerActions>,
+    mut gorilla_query: Query<(Entity, &Gorilla, &mut Transform, &mut Stride), Without<Airborne>>,
+    brick_query: Bricks,
+) {
+    let input = actions.get(player.get());
+    let direction = match (
+        input.pressed(GameAction::WalkLeft),
+        input.pressed(GameAction::WalkRight),
+    ) {
+        (true, false) => -1.0,
+        (false, true) => 1.0,
+        _ => return,
+    };
+    for (e, g, mut transform, mut stride) in gorilla_query.iter_mut() {
+        if &g.player != player.get() {
+            continue;
+        }
+        stride.facing = direction;
+        let step = (GORILLA_WALK_SPEED * time.delta_seconds()).min(stride.left);
+        if step <= 0.0 {
+            return;
+        }
+        let position = transform.translation.truncate() + Vec2::new(direction * step, 0.0);
+        // walls and taller buildings are in the way
+        if blocked(position, &brick_query) {
+            return;
+        }
+        stride.left -= step;
+        transform.translation.x = position.x;
+        if !standing(position, &brick_query) {
+            info!("{:?} walked off the edge", g.player);
+            take_off(&mut commands, e, position, Vec2::ZERO);
+        }
+    }
+}
+
+fn jump(
+    mut commands: Commands,
+    player: Res<State<Player>>,
+    mut gorilla_query: Query<(Entity, &Gorilla, &Transform, &mut Stride), Without<Airborne>>,
+) {
+    for (e, g, transform, mut stride) in gorilla_query.iter_mut() {
+        if &g.player == player.get() && !stride.jumped {
+            info!("{:?} jumped", g.player);
+            stride.jumped = true;
+            take_off(
+                &mut commands,
+                e,
+                transform.translation.truncate(),
+                Vec2::new(GORILLA_JUMP.x * stride.facing, GORILLA_JUMP.y),
+            );
+        }
+    }
+}
+
+fn take_off(commands: &mut Commands, gorilla: Entity, position: Vec2, velocity: Vec2) {
+    // gorillas are too heavy for the wind
+    commands.entity(gorilla).insert((
+        Airborne,
+        Velocity(velocity),
+        MovementState::new(position),
+        WindFactor(0.0),
+    ));
+}
+
+/// come down on the first brick under their feet, or fall out of the arena
+fn land(
+    mut commands: Commands,
+    level: Res<Level>,
+    mut gorilla_query: Query<
+        (
+            Entity,
+            &Gorilla,
+            &mut Transform,
+            &mut Velocity,
+            &mut MovementState,
+        ),
+        With<Airborne>,
+    >,
+    brick_query: Bricks,
+    mut fell_events: EventWriter<GorillaFellEvent>,
+) {
+    for (e, g, mut transform, mut velocity, mut movement) in gorilla_query.iter_mut() {
+        let position = movement.position();
+        let previous = movement.previous_position();
+        if position.y + GORILLA_HEIGHT / 2.0 < -level.height / 2.0 {
+            info!("{:?} fell out of the arena", g.player);
+            commands
+                .entity(e)
+                .remove::<(Airborne, Velocity, MovementState, WindFactor)>();
+            fell_events.send(GorillaFellEvent {
+                player: g.player.clone(),
+            });
+            continue;
+        }
+        let feet = position.y - GORILLA_HEIGHT / 2.0;
+        let previous_feet = previous.y - GORILLA_HEIGHT / 2.0;
+        let roof = brick_query
+            .iter()
+            .filter(|t| under_feet(position, t))
+            .map(|t| t.translation.y + t.scale.y / 2.0)
+            .filter(|top| previous_feet + FOOTING >= *top && feet <= *top + FOOTING)
+            .reduce(f32::max);
+        match roof {
+            Some(roof) if velocity.y <= 0.0 => {
+                info!("{:?} landed", g.player);
+                transform.translation.y = roof + GORILLA_HEIGHT / 2.0;
+                transform.translation.x = position.x;
+                commands
+                    .entity(e)
+                    .remove::<(Airborne, Velocity, MovementState, WindFactor)>();
+            }
+            // bump into the sides of buildings and drop straight down
+            _ if blocked(position, &brick_query)
+                && !blocked(Vec2::new(previous.x, position.y), &brick_query) =>
+            {
+                velocity.x = 0.0;
+                movement.teleport(Vec2::new(previous.x, position.y));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// the gorilla is over this brick, hanging off its edge by up to a quarter of their
+/// width so they do not slip between two bricks that touch
+fn under_feet(position: Vec2, brick: &Transform) -> bool {
+    (brick.translation.x - position.x).abs() < (brick.scale.x + GORILLA_WIDTH / 2.0) / 2.0
+}
+
+/// there is a brick right under the feet of a gorilla at this position
+fn standing(position: Vec2, brick_query: &Bricks) -> bool {
+    let feet = position.y - GORILLA_HEIGHT / 2.0;
+    brick_query
+        .iter()
+        .filter(|t| under_feet(position, t))
+        .any(|t| (t.translation.y + t.scale.y / 2.0 - feet).abs() < FOOTING)
+}
+
+/// a gorilla at this position would be inside a brick
+fn blocked(position: Vec2, brick_query: &Bricks) -> bool {
+    // a little above their feet, so the roof they stand on does not count
+    let body = Aabb2d::new(
+        position + Vec2::new(0.0, FOOTING),
+        Vec2::new(GORILLA_WIDTH, GORILLA_HEIGHT) / 2.0 - FOOTING,
+    );
+    brick_query.iter().any(|t| {
+        body.intersects(&Aabb2d::new(
+            t.translation.truncate(),
+            t.scale.truncate() / 2.0,
+        ))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use crate::skyline::SkylineStyle;
+
+    use super::*;
+
+    const STEP: Duration = Duration::from_millis(100);
+    const ROOF: f32 = BUILDING_BRICK_HEIGHT / 2.0;
+
+    fn app() -> App {
+        let mut app = App::new();
+        // every update is a step long, as nothing moves the clocks on
+        let mut time = Time::<()>::default();
+        time.advance_by(STEP);
+        let mut fixed_time = Time::<Fixed>::from_duration(STEP);
+        fixed_time.advance_by(STEP);
+        app.insert_resource(time)
+            .insert_resource(fixed_time)
+            .insert_resource(State::new(Player::One))
+            .insert_resource(Level::generate(
+                SkylineStyle::Uniform,
+                None,
+                1,
+                ARENA_WIDTH,
+                ARENA_HEIGHT,
+            ))
+            .init_resource::<PlayerActions>()
+            .add_event::<GorillaFellEvent>()
+            .add_systems(Update, (walk, (apply_velocity, land).chain()));
+        app
+    }
+
+    fn spawn_brick(app: &mut App, x: f32, y: f32) {
+        app.world.spawn((
+            BuildingBrick::new(crate::level::BrickMaterial::Brick),
+            Transform {
+                translation: Vec3::new(x, y, 0.0),
+                scale: Vec3::new(BUILDING_BRICK_WIDTH, BUILDING_BRICK_HEIGHT, 1.0),
+                ..default()
+            },
+            Collider,
+        ));
+    }
+
+    /// a roof from -60 to 60 with its top at `ROOF`
+    fn spawn_roof(app: &mut App) {
+        for i in -2..=2 {
+            spawn_brick(app, i as f32 * BUILDING_BRICK_WIDTH, 0.0);
+        }
+    }
+
+    fn spawn_gorilla(app: &mut App, x: f32) -> Entity {
+        app.world
+            .spawn((
+                Gorilla::one("One".to_string()),
+                Transform::from_xyz(x, ROOF + GORILLA_HEIGHT / 2.0, 0.0),
+                Stride::new(&Player::One),
+            ))
+            .id()
+    }
+
+    fn launch(app: &mut App, gorilla: Entity, position: Vec2, velocity: Vec2) {
+        app.world.entity_mut(gorilla).insert((
+            Airborne,
+            Velocity(velocity),
+            MovementState::new(position),
+        ));
+    }
+
+    fn walk_right(app: &mut App) {
+        let mut actions = app.world.resource_mut::<PlayerActions>();
+        actions.get_mut(&Player::One).press(GameAction::WalkRight);
+    }
+
+    #[test]
+    fn walks_along_the_roof_until_a_wall() {
+        let mut app = app();
+        spawn_roof(&mut app);
+        // a wall on the roof starting at x=30
+        for row in 1..=8 {
+            spawn_brick(&mut app, 40.0, row as f32 * BUILDING_BRICK_HEIGHT);
+        }
+        let gorilla = spawn_gorilla(&mut app, 0.0);
+        walk_right(&mut app);
+        app.update();
+        let x = app.world.get::<Transform>(gorilla).unwrap().translation.x;
+        assert_eq!(x, GORILLA_WALK_SPEED * STEP.as_secs_f32());
+
+        for _ in 0..10 {
+            app.update();
+        }
+        let x = app.world.get::<Transform>(gorilla).unwrap().translation.x;
+        assert!(
+            x + GORILLA_WIDTH / 2.0 <= 30.0 + FOOTING,
+            "walked into the wall at {x}"
+        );
+        assert!(app.world.get::<Airborne>(gorilla).is_none());
+    }
+
+    #[test]
+    fn walking_off_the_edge_takes_off() {
+        let mut app = app();
+        spawn_roof(&mut app);
+        let gorilla = spawn_gorilla(&mut app, 55.0);
+        walk_right(&mut app);
+        for _ in 0..3 {
+            app.update();
+        }
+        assert!(app.world.get::<Airborne>(gorilla).is_some());
+    }
+
+    #[test]
+    fn lands_on_the_roof() {
+        let mut app = app();
+        spawn_roof(&mut app);
+        let gorilla = spawn_gorilla(&mut app, 0.0);
+        let above = Vec2::new(10.0, ROOF + GORILLA_HEIGHT / 2.0 + 4.0);
+        launch(&mut app, gorilla, above, Vec2::new(0.0, -50.0));
+        app.update();
+        assert!(app.world.get::<Airborne>(gorilla).is_none());
+        let t = app.world.get::<Transform>(gorilla).unwrap().translation;
+        assert_eq!(t.truncate(), Vec2::new(10.0, ROOF + GORILLA_HEIGHT / 2.0));
+    }
+
+    #[test]
+    fn bumps_into_walls_and_drops() {
+        let mut app = app();
+        for row in 0..8 {
+            spawn_brick(&mut app, 40.0, row as f32 * BUILDING_BRICK_HEIGHT);
+        }
+        let gorilla = spawn_gorilla(&mut app, 0.0);
+        launch(
+            &mut app,
+            gorilla,
+            Vec2::new(10.0, 30.0),
+            Vec2::new(100.0, 0.0),
+        );
+        app.update();
+        assert_eq!(app.world.get::<Velocity>(gorilla).unwrap().x, 0.0);
+        let position = app.world.get::<MovementState>(gorilla).unwrap().position();
+        assert_eq!(position.x, 10.0);
+    }
+
+    #[test]
+    fn falling_out_of_the_arena_is_reported() {
+        let mut app = app();
+        let gorilla = spawn_gorilla(&mut app, 0.0);
+        let bottom = -ARENA_HEIGHT / 2.0 - GORILLA_HEIGHT / 2.0;
+        launch(
+            &mut app,
+            gorilla,
+            Vec2::new(0.0, bottom + 1.0),
+            Vec2::new(0.0, -50.0),
+        );
+        app.update();
+        assert!(app.world.get::<Airborne>(gorilla).is_none());
+        let fell: Vec<_> = app
+            .world
+            .resource_mut::<Events<GorillaFellEvent>>()
+            .drain()
+            .map(|e| e.player)
+            .collect();
+        assert_eq!(fell, [Player::One]);
+    }
+}
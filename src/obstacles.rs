@@ -0,0 +1,146 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+
+use crate::level::Level;
+use crate::prelude::*;
+use crate::rules::MatchRules;
+
+// room left above the tallest building and below the top of the arena
+const SKY_MARGIN: f32 = 60.0;
+
+/// Something crossing the sky that bananas can hit
+#[derive(Component, Debug, Clone, Copy)]
+pub(crate) struct Obstacle {
+    pub(crate) kind: ObstacleKind,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ObstacleKind {
+    /// small and quick, bananas bounce off
+    Bird,
+    /// big and slow, drops a bonus when it pops
+    Blimp,
+    Plane,
+}
+
+impl ObstacleKind {
+    fn size(&self) -> Vec2 {
+        match self {
+            ObstacleKind::Bird => Vec2::new(16.0, 8.0),
+            ObstacleKind::Blimp => Vec2::new(96.0, 36.0),
+            ObstacleKind::Plane => Vec2::new(48.0, 12.0),
+        }
+    }
+
+    /// in pixels per second
+    fn speed(&self) -> f32 {
+        match self {
+            ObstacleKind::Bird => 120.0,
+            ObstacleKind::Blimp => 40.0,
+            ObstacleKind::Plane => 220.0,
+        }
+    }
+
+    fn color(&self) -> Color {
+        match self {
+            ObstacleKind::Bird => Color::rgb(0.2, 0.2, 0.2),
+            ObstacleKind::Blimp => Color::rgb(0.75, 0.75, 0.8),
+            ObstacleKind::Plane => Color::rgb(0.95, 0.95, 0.95),
+        }
+    }
+
+    /// bananas bounce off rather than blowing up on it
+    pub(crate) fn deflects(&self) -> bool {
+        *self == ObstacleKind::Bird
+    }
+
+    pub(crate) fn drops_bonus(&self) -> bool {
+        *self == ObstacleKind::Blimp
+    }
+}
+
+/// A banana or a blast hit an obstacle, it is gone afterwards
+#[derive(Event, Debug)]
+pub(crate) struct ObstacleHitEvent {
+    pub(crate) obstacle: Entity,
+    pub(crate) kind: ObstacleKind,
+    pub(crate) position: Vec2,
+    pub(crate) drops_bonus: bool,
+}
+
+pub(crate) struct ObstaclesPlugin;
+impl Plugin for ObstaclesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<ObstacleHitEvent>()
+            .add_systems(Startup, setup_obstacles)
+            .add_systems(Update, (wrap_obstacles, remove_hit_obstacles));
+    }
+}
+
+fn setup_obstacles(mut commands: Commands, rules: Res<MatchRules>, level: Res<Level>) {
+    if rules.obstacles == 0 {
+        return;
+    }
+    // the same seed flies the same paths
+    let mut rng = StdRng::seed_from_u64(rules.seed);
+    let tallest = level.buildings.iter().map(|b| b.height).fold(0.0, f32::max);
+    let lowest = (tallest + SKY_MARGIN).min(level.height - SKY_MARGIN);
+    let highest = (level.height - SKY_MARGIN).max(lowest);
+
+    for _ in 0..rules.obstacles {
+        let kind = match rng.gen_range(0..3) {
+            0 => ObstacleKind::Bird,
+            1 => ObstacleKind::Blimp,
+            _ => ObstacleKind::Plane,
+        };
+        let direction = if rng.gen_bool(0.5) { 1.0 } else { -1.0 };
+        let position = Vec2::new(
+            rng.gen_range(0.0..level.width) - level.width / 2.0,
+            rng.gen_range(lowest..=highest) - level.height / 2.0,
+        );
+        commands.spawn((
+            Obstacle { kind },
+            SpriteBundle {
+                transform: Transform {
+                    translation: position.extend(OBSTACLE_Z_INDEX),
+                    scale: kind.size().extend(1.0),
+                    ..default()
+                },
+                sprite: Sprite {
+                    color: kind.color(),
+                    ..default()
+                },
+                ..default()
+            },
+            Velocity(Vec2::new(kind.speed() * direction, 0.0)),
+            MovementState::new(position),
+            Floating,
+            Collider,
+        ));
+    }
+}
+
+/// once across the sky start again from the other side
+fn wrap_obstacles(level: Res<Level>, mut query: Query<(&Obstacle, &mut MovementState)>) {
+    for (obstacle, mut movement) in query.iter_mut() {
+        let edge = (level.width + obstacle.kind.size().x) / 2.0;
+        let position = movement.position();
+        if position.x > edge {
+            movement.teleport(Vec2::new(-edge, position.y));
+        } else if position.x < -edge {
+            movement.teleport(Vec2::new(edge, position.y));
+        }
+    }
+}
+
+fn remove_hit_obstacles(mut commands: Commands, mut events: EventReader<ObstacleHitEvent>) {
+    for event in events.read() {
+        info!(
+            "{:?} hit at {}, drops a bonus: {}",
+            event.kind, event.position, event.drops_bonus
+        );
+        if let Some(mut obstacle) = commands.get_entity(event.obstacle) {
+            obstacle.despawn();
+        }
+    }
+}
@@ -1,5 +1,8 @@
+use std::f32::consts::PI;
+
 use crate::constants::TIME_STEP;
 use crate::game::{Explosion, InGameplaySet};
+use crate::level::Level;
 use crate::prelude::*;
 
 #[derive(Component, Deref, DerefMut, Debug)]
@@ -24,11 +27,34 @@ impl MovementState {
             cur_position: current_position,
         }
     }
+
+    pub(crate) fn position(&self) -> Vec2 {
+        self.cur_position
+    }
+
+    /// where it was before the last physics step
+    pub(crate) fn previous_position(&self) -> Vec2 {
+        self.old_position
+    }
+
+    /// move straight there without passing through the positions in between
+    pub(crate) fn teleport(&mut self, position: Vec2) {
+        self.old_position = position;
+        self.cur_position = position;
+    }
 }
 
 #[derive(Component)]
 pub(crate) struct Gravity;
 
+/// How much the wind pushes something around, everything else feels all of it
+#[derive(Component, Deref, DerefMut, Debug)]
+pub(crate) struct WindFactor(pub(crate) f32);
+
+/// Keeps its velocity, neither gravity nor the wind move it
+#[derive(Component)]
+pub(crate) struct Floating;
+
 pub(crate) struct PhysicsPlugin;
 impl Plugin for PhysicsPlugin {
     fn build(&self, app: &mut App) {
@@ -48,10 +74,10 @@ impl Plugin for PhysicsPlugin {
 
 fn animate_explosion(
     mut commands: Commands,
-    mut explosion_query: Query<(Entity, &mut Transform), With<Explosion>>,
+    mut explosion_query: Query<(Entity, &Explosion, &mut Transform)>,
 ) {
-    for (e, ref mut t) in explosion_query.iter_mut() {
-        if t.scale.x > EXPLOSION_SIZE {
+    for (e, explosion, ref mut t) in explosion_query.iter_mut() {
+        if t.scale.x > explosion.size {
             commands.entity(e).despawn_recursive();
         } else {
             t.scale *= 1.0 + EXPLOSION_SPEED * TIME_STEP;
@@ -59,30 +85,36 @@ fn animate_explosion(
     }
 }
 
-fn setup_gravity(mut commands: Commands) {
+fn setup_gravity(mut commands: Commands, level: Res<Level>) {
     commands.spawn((
         Gravity,
-        GlobalWorldAcceleration(Vec2::new(0.0, GRAVITY_Y_ACCEL)),
+        GlobalWorldAcceleration(level.gravity_acceleration()),
     ));
 }
 
 fn apply_acceleration(
     fixed_time: Res<Time<Fixed>>,
-    acceleration_query: Query<&GlobalWorldAcceleration>,
-    mut velocity_query: Query<&mut Velocity>,
+    gravity_query: Query<&GlobalWorldAcceleration, With<Gravity>>,
+    wind_query: Query<&GlobalWorldAcceleration, Without<Gravity>>,
+    mut velocity_query: Query<(&mut Velocity, Option<&WindFactor>), Without<Floating>>,
 ) {
-    for acc in acceleration_query.iter() {
-        for mut velocity in velocity_query.iter_mut() {
-            velocity.x += acc.x * fixed_time.delta_seconds();
-            velocity.y += acc.y * fixed_time.delta_seconds();
-        }
+    let gravity = gravity_query.iter().map(|acc| acc.0).sum();
+    let wind: Vec2 = wind_query.iter().map(|acc| acc.0).sum();
+    for (mut velocity, wind_factor) in velocity_query.iter_mut() {
+        let factor = wind_factor.map_or(1.0, |f| f.0);
+        let acceleration = world_acceleration(gravity, wind_acceleration(wind, factor));
+        velocity.0 = accelerate(velocity.0, acceleration, fixed_time.delta_seconds());
     }
 }
 
-fn apply_velocity(fixed_time: Res<Time<Fixed>>, mut query: Query<(&Velocity, &mut MovementState)>) {
+pub(crate) fn apply_velocity(fixed_time: Res<Time<Fixed>>, mut query: Query<(&Velocity, &mut MovementState)>) {
     for (velocity, mut movement) in query.iter_mut() {
         movement.old_position = movement.cur_position;
-        movement.cur_position += velocity.0 * fixed_time.delta_seconds();
+        movement.cur_position = advance(
+            movement.cur_position,
+            velocity.0,
+            fixed_time.delta_seconds(),
+        );
     }
 }
 
@@ -104,3 +136,151 @@ fn apply_rotation(mut query: Query<(&mut Transform, &Rotation)>) {
         transform.rotation *= rotation.0;
     }
 }
+
+// The functions below are the whole of the motion model. The systems above and
+// `simulate_throw` both go through them so that a simulated throw lands exactly
+// where the real banana does.
+
+/// the push of the wind on something that only feels `wind_factor` of it
+pub(crate) fn wind_acceleration(wind: Vec2, wind_factor: f32) -> Vec2 {
+    wind * wind_factor
+}
+
+pub(crate) fn world_acceleration(gravity: Vec2, wind: Vec2) -> Vec2 {
+    gravity + wind
+}
+
+pub(crate) fn accelerate(velocity: Vec2, acceleration: Vec2, dt: f32) -> Vec2 {
+    velocity + acceleration * dt
+}
+
+pub(crate) fn advance(position: Vec2, velocity: Vec2, dt: f32) -> Vec2 {
+    position + velocity * dt
+}
+
+/// initial velocity of a banana thrown by `player` with the given angle and speed
+pub(crate) fn launch_velocity(player: &Player, angle: u8, speed: u8) -> Vec2 {
+    // if left alone compass looks like this, but we want to make 90 straight up
+    // and for 100 to be behind the head
+    //        0
+    // 270 <- * -> 90
+    //       180
+    //
+    // so we are just going to do (90 - *degrees*)
+    // to make it go
+    //       90
+    // 180 <- * -> 0
+    //       270
+    let radians = (90f32 - (angle as f32)) * PI / 180.0;
+    let mut v = Vec2::new(
+        radians.sin() * (speed as f32),
+        radians.cos() * (speed as f32),
+    );
+
+    // scale, then reverse for player 2
+    v *= PIXEL_STEP_SIZE / 1.5;
+    if player == &Player::Two {
+        v.x *= -1.0
+    }
+    v
+}
+
+/// Positions of a banana thrown from `start`, one per physics step of `dt` seconds.
+/// `wind_factor` is the `WindFactor` the banana will be thrown with.
+///
+/// The iterator never ends, callers decide when the banana has landed.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn simulate_throw(
+    start: Vec2,
+    angle: u8,
+    speed: u8,
+    player: &Player,
+    wind: Vec2,
+    wind_factor: f32,
+    gravity: Vec2,
+    dt: f32,
+) -> ThrowSimulation {
+    ThrowSimulation {
+        position: start,
+        velocity: launch_velocity(player, angle, speed),
+        acceleration: world_acceleration(gravity, wind_acceleration(wind, wind_factor)),
+        dt,
+    }
+}
+
+pub(crate) struct ThrowSimulation {
+    position: Vec2,
+    velocity: Vec2,
+    acceleration: Vec2,
+    dt: f32,
+}
+
+impl Iterator for ThrowSimulation {
+    type Item = Vec2;
+
+    fn next(&mut self) -> Option<Vec2> {
+        self.velocity = accelerate(self.velocity, self.acceleration, self.dt);
+        self.position = advance(self.position, self.velocity, self.dt);
+        Some(self.position)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    const GRAVITY: Vec2 = Vec2::new(0.0, GRAVITY_Y_ACCEL);
+    const WIND: Vec2 = Vec2::new(-7.0, 0.0);
+    const START: Vec2 = Vec2::new(-500.0, -100.0);
+
+    /// step a banana through the real systems and the simulation side by side
+    fn assert_same_path(player: Player, angle: u8, speed: u8, wind_factor: Option<f32>) {
+        let timestep = Duration::from_secs_f64(1.0 / FIXED_HZ);
+        let dt = timestep.as_secs_f32();
+        let mut app = App::new();
+        app.insert_resource(Time::<Fixed>::from_duration(timestep))
+            .add_systems(Update, (apply_acceleration, apply_velocity).chain());
+        app.world.spawn((Gravity, GlobalWorldAcceleration(GRAVITY)));
+        app.world.spawn(GlobalWorldAcceleration(WIND));
+        let mut banana = app.world.spawn((
+            Velocity(launch_velocity(&player, angle, speed)),
+            MovementState::new(START),
+        ));
+        if let Some(factor) = wind_factor {
+            banana.insert(WindFactor(factor));
+        }
+        let banana = banana.id();
+
+        let simulated = simulate_throw(
+            START,
+            angle,
+            speed,
+            &player,
+            WIND,
+            wind_factor.unwrap_or(1.0),
+            GRAVITY,
+            dt,
+        );
+        for (step, expected) in simulated.take(300).enumerate() {
+            app.world.resource_mut::<Time<Fixed>>().advance_by(timestep);
+            app.update();
+            let actual = app.world.get::<MovementState>(banana).unwrap().position();
+            assert_eq!(actual, expected, "step {step}");
+        }
+    }
+
+    #[test]
+    fn simulation_matches_the_game() {
+        assert_same_path(Player::One, 45, 50, None);
+        assert_same_path(Player::Two, 70, 35, None);
+    }
+
+    #[test]
+    fn simulation_matches_the_game_with_a_wind_factor() {
+        assert_same_path(Player::One, 60, 40, Some(0.3));
+        // a calm throw ignores the wind altogether
+        assert_same_path(Player::Two, 30, 60, Some(0.0));
+    }
+}
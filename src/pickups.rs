@@ -0,0 +1,273 @@
+use std::mem;
+
+use bevy::ecs::system::EntityCommands;
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
+
+use crate::defense::{raise_shield, Shield};
+use crate::game::InGameplaySet;
+use crate::level::Level;
+use crate::obstacles::ObstacleHitEvent;
+use crate::prelude::*;
+use crate::rules::MatchRules;
+use crate::theme::Theme;
+
+// how high above a roof a crate floats
+const CRATE_HOVER: f32 = 90.0;
+// how far crates bob up and down, in pixels
+const CRATE_BOB: f32 = 4.0;
+const ANNOUNCEMENT_SECONDS: f32 = 3.0;
+
+const PICKUPS: [PickupKind; 4] = [
+    PickupKind::ExtraShot,
+    PickupKind::Calm,
+    PickupKind::BigBlast,
+    PickupKind::Shield,
+];
+
+/// A crate floating in the sky, bananas pick it up by going through it
+#[derive(Component)]
+pub(crate) struct Crate {
+    pub(crate) pickup: PickupKind,
+    hover_y: f32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum PickupKind {
+    /// throw again straight after this throw
+    ExtraShot,
+    /// the wind does not touch the next throw
+    Calm,
+    /// the next throw blows up twice as big
+    BigBlast,
+    /// survive the next explosion
+    Shield,
+}
+
+impl PickupKind {
+    fn color(&self) -> Color {
+        match self {
+            PickupKind::ExtraShot => Color::rgb(0.3, 0.8, 0.3),
+            PickupKind::Calm => Color::rgb(0.55, 0.8, 1.0),
+            PickupKind::BigBlast => Color::rgb(1.0, 0.5, 0.1),
+            PickupKind::Shield => SHIELD_COLOR,
+        }
+    }
+
+    fn description(&self) -> &'static str {
+        match self {
+            PickupKind::ExtraShot => "an extra shot",
+            PickupKind::Calm => "a throw the wind can't touch",
+            PickupKind::BigBlast => "a bigger blast",
+            PickupKind::Shield => "a shield",
+        }
+    }
+}
+
+/// A banana went through a crate
+#[derive(Event, Debug)]
+pub(crate) struct PickupEvent {
+    pub(crate) crate_entity: Entity,
+    pub(crate) pickup: PickupKind,
+    pub(crate) player: Player,
+}
+
+/// Pickups a gorilla is saving up
+#[derive(Component, Default)]
+pub(crate) struct PowerUps {
+    extra_shot: bool,
+    calm: bool,
+    big_blast: bool,
+}
+
+impl PowerUps {
+    /// how much the wind will push the next throw
+    pub(crate) fn wind_factor(&self) -> f32 {
+        if self.calm {
+            0.0
+        } else {
+            1.0
+        }
+    }
+
+    /// boost the banana that was just thrown with anything saved up for it
+    pub(crate) fn use_on(&mut self, banana: &mut EntityCommands) {
+        if mem::take(&mut self.calm) {
+            banana.insert(WindFactor(0.0));
+        }
+        if mem::take(&mut self.big_blast) {
+            banana.insert(BigBlast);
+        }
+    }
+
+    pub(crate) fn take_extra_shot(&mut self) -> bool {
+        mem::take(&mut self.extra_shot)
+    }
+}
+
+/// The banana blows up twice as big
+#[derive(Component)]
+pub(crate) struct BigBlast;
+
+/// Tells everyone who picked up what
+#[derive(Component)]
+struct Announcement {
+    timer: Timer,
+}
+
+pub(crate) struct PickupsPlugin;
+impl Plugin for PickupsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<PickupEvent>()
+            .add_systems(Startup, (setup_crates, setup_announcement))
+            .add_systems(
+                Update,
+                (
+                    bob_crates,
+                    drop_crates,
+                    // before the turn changes so an extra shot counts straight away
+                    collect_pickups
+                        .after(InGameplaySet::Collisions)
+                        .before(InGameplaySet::TurnChanges),
+                    hide_announcement,
+                ),
+            );
+    }
+}
+
+fn setup_crates(mut commands: Commands, rules: Res<MatchRules>, level: Res<Level>) {
+    // not above the gorillas, they would pick them up as they throw
+    let roofs: Vec<_> = level
+        .buildings
+        .iter()
+        .filter(|b| {
+            [level.spawns.player_one, level.spawns.player_two]
+                .iter()
+                .all(|x| *x < b.x || *x > b.x + b.width)
+        })
+        .collect();
+    if rules.pickups == 0 || roofs.is_empty() {
+        return;
+    }
+    // the same seed hides the same crates
+    let mut rng = StdRng::seed_from_u64(rules.seed);
+    for _ in 0..rules.pickups {
+        let b = roofs[rng.gen_range(0..roofs.len())];
+        let position = Vec2::new(
+            b.x + b.width / 2.0 - level.width / 2.0,
+            (b.height + CRATE_HOVER).min(level.height - CRATE_SIZE) - level.height / 2.0,
+        );
+        spawn_crate(
+            &mut commands,
+            PICKUPS[rng.gen_range(0..PICKUPS.len())],
+            position,
+        );
+    }
+}
+
+fn setup_announcement(mut commands: Commands, asset_server: Res<AssetServer>, theme: Res<Theme>) {
+    let mut timer = Timer::from_seconds(ANNOUNCEMENT_SECONDS, TimerMode::Once);
+    timer.tick(timer.duration());
+    commands.spawn((
+        Announcement { timer },
+        TextBundle {
+            text: Text::from_section(
+                "",
+                TextStyle {
+                    font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                    font_size: 30.0,
+                    color: theme.palette().text,
+                },
+            )
+            .with_justify(JustifyText::Center),
+            style: Style {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(10.0),
+                width: Val::Percent(100.0),
+                ..default()
+            },
+            ..default()
+        },
+    ));
+}
+
+fn spawn_crate(commands: &mut Commands, pickup: PickupKind, position: Vec2) {
+    commands.spawn((
+        Crate {
+            pickup,
+            hover_y: position.y,
+        },
+        SpriteBundle {
+            transform: Transform {
+                translation: position.extend(CRATE_Z_INDEX),
+                scale: Vec2::splat(CRATE_SIZE).extend(1.0),
+                ..default()
+            },
+            sprite: Sprite {
+                color: pickup.color(),
+                ..default()
+            },
+            ..default()
+        },
+        Collider,
+    ));
+}
+
+fn bob_crates(time: Res<Time>, mut query: Query<(&Crate, &mut Transform)>) {
+    for (c, mut transform) in query.iter_mut() {
+        // out of step with each other so they do not all bob together
+        let phase = time.elapsed_seconds() * 2.0 + transform.translation.x / 100.0;
+        transform.translation.y = c.hover_y + phase.sin() * CRATE_BOB;
+    }
+}
+
+/// popped blimps leave a crate behind
+fn drop_crates(mut commands: Commands, mut events: EventReader<ObstacleHitEvent>) {
+    let mut rng = thread_rng();
+    for event in events.read().filter(|e| e.drops_bonus) {
+        let pickup = PICKUPS[rng.gen_range(0..PICKUPS.len())];
+        info!("{:?} dropped a {:?} crate", event.kind, pickup);
+        spawn_crate(&mut commands, pickup, event.position);
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    mut events: EventReader<PickupEvent>,
+    mut gorilla_query: Query<(Entity, &Gorilla, &mut PowerUps, Option<&Shield>)>,
+    mut announcement_query: Query<(&mut Text, &mut Announcement)>,
+) {
+    for event in events.read() {
+        if let Some(mut c) = commands.get_entity(event.crate_entity) {
+            c.despawn();
+        }
+        let Some((gorilla, g, mut power_ups, shield)) = gorilla_query
+            .iter_mut()
+            .find(|(_, g, _, _)| g.player == event.player)
+        else {
+            continue;
+        };
+        info!("{:?} picked up {:?}", event.player, event.pickup);
+        match event.pickup {
+            PickupKind::ExtraShot => power_ups.extra_shot = true,
+            PickupKind::Calm => power_ups.calm = true,
+            PickupKind::BigBlast => power_ups.big_blast = true,
+            PickupKind::Shield if shield.is_none() => {
+                raise_shield(&mut commands, gorilla, Shield::until_used())
+            }
+            PickupKind::Shield => {}
+        }
+        for (mut text, mut announcement) in announcement_query.iter_mut() {
+            text.sections[0].value = format!("{} got {}!", g.name, event.pickup.description());
+            announcement.timer.reset();
+        }
+    }
+}
+
+fn hide_announcement(time: Res<Time>, mut query: Query<(&mut Text, &mut Announcement)>) {
+    for (mut text, mut announcement) in query.iter_mut() {
+        if announcement.timer.tick(time.delta()).just_finished() {
+            text.sections[0].value.clear();
+        }
+    }
+}
@@ -0,0 +1,147 @@
+use crate::aim::TypedAim;
+use crate::game::{Action, AngleSpeed};
+use crate::prelude::*;
+use crate::projectile::Ammo;
+use crate::rules::MatchRules;
+
+/// Who has locked in their throw during planning, everything is thrown at once when
+/// everyone has
+#[derive(Resource, Default, Debug)]
+pub(crate) struct Plans {
+    locked_in: Vec<Player>,
+}
+
+impl Plans {
+    /// everyone with something left to throw has locked in
+    pub(crate) fn ready<'a>(&self, mut throwers: impl Iterator<Item = &'a Player>) -> bool {
+        throwers.all(|p| self.locked_in.contains(p))
+    }
+
+    pub(crate) fn locked_in(&self) -> &[Player] {
+        &self.locked_in
+    }
+}
+
+pub(crate) struct PlanningPlugin;
+impl Plugin for PlanningPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Plans>()
+            // once the first turn has been set up, which would otherwise take it back to
+            // entering a throw
+            .add_systems(Update, start_planning.run_if(run_once()))
+            .add_systems(OnEnter(Action::Planning), reset_plans);
+    }
+}
+
+fn start_planning(rules: Res<MatchRules>, mut next_action: ResMut<NextState<Action>>) {
+    if rules.simultaneous {
+        next_action.set(Action::Planning);
+    }
+}
+
+fn reset_plans(mut plans: ResMut<Plans>, mut typed_aim: ResMut<TypedAim>) {
+    plans.locked_in.clear();
+    *typed_aim = TypedAim::default();
+}
+
+/// the current player is done aiming, hand over to whoever has not planned yet
+pub(crate) fn lock_in(
+    player: Res<State<Player>>,
+    mut next_player: ResMut<NextState<Player>>,
+    mut plans: ResMut<Plans>,
+    mut typed_aim: ResMut<TypedAim>,
+    gorilla_query: Query<(&Gorilla, &AngleSpeed, &Ammo)>,
+) {
+    if plans.locked_in.contains(player.get()) {
+        return;
+    }
+    if let Some((_, a, _)) = gorilla_query
+        .iter()
+        .find(|(g, _, _)| &g.player == player.get())
+    {
+        // only the log knows, the other player should not see it
+        debug!(
+            "{:?} locked in {}(m/s) @ {}°",
+            player.get(),
+            a.speed,
+            a.angle
+        );
+    }
+    info!("{:?} locked in their throw", player.get());
+    plans.locked_in.push(player.get().clone());
+    *typed_aim = TypedAim::default();
+
+    if let Some((g, _, _)) = gorilla_query
+        .iter()
+        .find(|(g, _, ammo)| !ammo.is_empty() && !plans.locked_in.contains(&g.player))
+    {
+        next_player.set(g.player.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn app(ammo_two: &str) -> App {
+        let mut app = App::new();
+        app.init_resource::<Plans>()
+            .init_resource::<TypedAim>()
+            .init_state::<Player>()
+            .add_systems(Update, lock_in);
+        let aim = || AngleSpeed {
+            angle: 45,
+            speed: 50,
+        };
+        app.world.spawn((
+            Gorilla::one("One".to_string()),
+            aim(),
+            "3".parse::<Ammo>().unwrap(),
+        ));
+        app.world.spawn((
+            Gorilla::two("Two".to_string()),
+            aim(),
+            ammo_two.parse::<Ammo>().unwrap(),
+        ));
+        app
+    }
+
+    fn ready(app: &App) -> bool {
+        app.world
+            .resource::<Plans>()
+            .ready([Player::One, Player::Two].iter())
+    }
+
+    #[test]
+    fn hands_over_to_whoever_has_not_planned() {
+        let mut app = app("3");
+        app.update();
+        assert_eq!(app.world.resource::<Plans>().locked_in(), &[Player::One]);
+        assert_eq!(
+            app.world.resource::<NextState<Player>>().0,
+            Some(Player::Two)
+        );
+        assert!(!ready(&app));
+
+        app.update();
+        assert_eq!(
+            app.world.resource::<Plans>().locked_in(),
+            &[Player::One, Player::Two]
+        );
+        assert!(ready(&app));
+        // everyone has planned, nobody to hand over to
+        assert_eq!(app.world.resource::<State<Player>>().get(), &Player::Two);
+    }
+
+    #[test]
+    fn skips_whoever_has_nothing_left_to_throw() {
+        let mut app = app("0");
+        app.update();
+        assert_eq!(app.world.resource::<Plans>().locked_in(), &[Player::One]);
+        assert_eq!(app.world.resource::<NextState<Player>>().0, None);
+
+        // locking in again changes nothing
+        app.update();
+        assert_eq!(app.world.resource::<Plans>().locked_in(), &[Player::One]);
+    }
+}
@@ -0,0 +1,344 @@
+use std::collections::HashMap;
+use std::f32::consts::PI;
+use std::str::FromStr;
+
+use crate::controls::{action_just_pressed, GameAction};
+use crate::game::{Action, InGameplaySet};
+use crate::prelude::*;
+
+/// Everything that can be thrown
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub(crate) enum ProjectileKind {
+    #[default]
+    Banana,
+    Coconut,
+    Cluster,
+    Rubber,
+    Drill,
+    /// one of the pieces a cluster banana splits into, it can not be picked
+    Fragment,
+}
+
+// the order the weapons are cycled through
+const WEAPONS: [ProjectileKind; 5] = [
+    ProjectileKind::Banana,
+    ProjectileKind::Coconut,
+    ProjectileKind::Cluster,
+    ProjectileKind::Rubber,
+    ProjectileKind::Drill,
+];
+
+/// How a kind of projectile looks and behaves
+pub(crate) struct ProjectileDef {
+    pub(crate) name: &'static str,
+    pub(crate) tint: Color,
+    /// relative to a banana
+    pub(crate) size: f32,
+    /// how far it turns every physics step
+    pub(crate) spin: f32,
+    /// how much the explosion grows before it is done
+    pub(crate) explosion_size: f32,
+    /// how much the wind pushes it around, gravity pulls everything the same
+    pub(crate) wind_factor: f32,
+    /// how many times it bounces off things before it blows up
+    pub(crate) bounces: u8,
+    /// how many bricks it drills through before it blows up
+    pub(crate) pierces: u8,
+    /// splits into fragments at the top of its flight
+    pub(crate) splits: bool,
+}
+
+const BANANA: ProjectileDef = ProjectileDef {
+    name: "Banana",
+    tint: Color::WHITE,
+    size: 1.0,
+    spin: PI * -TIME_STEP,
+    explosion_size: EXPLOSION_SIZE,
+    wind_factor: 1.0,
+    bounces: 0,
+    pierces: 0,
+    splits: false,
+};
+
+impl ProjectileKind {
+    pub(crate) fn def(self) -> ProjectileDef {
+        match self {
+            ProjectileKind::Banana => BANANA,
+            ProjectileKind::Coconut => ProjectileDef {
+                name: "Coconut",
+                tint: Color::rgb(0.55, 0.35, 0.2),
+                size: 1.3,
+                spin: PI * -TIME_STEP / 2.0,
+                explosion_size: EXPLOSION_SIZE * 1.8,
+                wind_factor: 0.3,
+                ..BANANA
+            },
+            ProjectileKind::Cluster => ProjectileDef {
+                name: "Cluster",
+                tint: Color::rgb(1.0, 0.6, 0.2),
+                splits: true,
+                ..BANANA
+            },
+            ProjectileKind::Rubber => ProjectileDef {
+                name: "Rubber",
+                tint: Color::rgb(1.0, 0.45, 0.65),
+                bounces: 3,
+                ..BANANA
+            },
+            ProjectileKind::Drill => ProjectileDef {
+                name: "Drill",
+                tint: Color::rgb(0.6, 0.6, 0.7),
+                spin: PI * -TIME_STEP * 4.0,
+                explosion_size: EXPLOSION_SIZE * 0.8,
+                wind_factor: 0.6,
+                pierces: 4,
+                ..BANANA
+            },
+            ProjectileKind::Fragment => ProjectileDef {
+                name: "Fragment",
+                tint: Color::rgb(1.0, 0.6, 0.2),
+                size: 0.6,
+                explosion_size: EXPLOSION_SIZE * 0.6,
+                ..BANANA
+            },
+        }
+    }
+
+    /// the weapon after this one that there is still some of, if any
+    fn next(self, ammo: &Ammo) -> Option<ProjectileKind> {
+        let i = WEAPONS.iter().position(|w| *w == self).unwrap_or(0);
+        (1..=WEAPONS.len())
+            .map(|step| WEAPONS[(i + step) % WEAPONS.len()])
+            .find(|w| ammo.has(*w))
+    }
+}
+
+impl FromStr for ProjectileKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "banana" => Ok(ProjectileKind::Banana),
+            "coconut" => Ok(ProjectileKind::Coconut),
+            "cluster" => Ok(ProjectileKind::Cluster),
+            "rubber" => Ok(ProjectileKind::Rubber),
+            "drill" => Ok(ProjectileKind::Drill),
+            _ => Err(format!(
+                "unknown weapon [{s}], expected banana, coconut, cluster, rubber or drill"
+            )),
+        }
+    }
+}
+
+/// What a gorilla is going to throw next
+#[derive(Component, Default, Deref, DerefMut)]
+pub(crate) struct Weapon(pub(crate) ProjectileKind);
+
+impl Weapon {
+    /// switch to something there is still some of, if this has run out
+    pub(crate) fn keep_in_stock(&mut self, ammo: &Ammo) {
+        if !ammo.has(self.0) {
+            if let Some(next) = self.next(ammo) {
+                self.0 = next;
+            }
+        }
+    }
+}
+
+/// What a gorilla has left to throw
+#[derive(Component, Debug, Clone, Default)]
+pub(crate) struct Ammo {
+    /// `None` for as many of everything as they like, otherwise anything
+    /// missing is not carried at all
+    counts: Option<HashMap<ProjectileKind, u32>>,
+}
+
+impl Ammo {
+    /// how many are left, `None` when there is no limit
+    pub(crate) fn left(&self, kind: ProjectileKind) -> Option<u32> {
+        self.counts
+            .as_ref()
+            .map(|counts| counts.get(&kind).copied().unwrap_or(0))
+    }
+
+    pub(crate) fn has(&self, kind: ProjectileKind) -> bool {
+        self.left(kind) != Some(0)
+    }
+
+    /// use one up, false when there are none left
+    pub(crate) fn take(&mut self, kind: ProjectileKind) -> bool {
+        match self
+            .counts
+            .as_mut()
+            .map(|counts| counts.entry(kind).or_default())
+        {
+            None => true,
+            Some(0) => false,
+            Some(count) => {
+                *count -= 1;
+                true
+            }
+        }
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        !WEAPONS.iter().any(|w| self.has(*w))
+    }
+}
+
+/// either a count of every weapon, `5`, or a count of just some, `banana=5,coconut=1`
+impl FromStr for Ammo {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let count = |value: &str| {
+            value
+                .parse::<u32>()
+                .map_err(|_| format!("invalid ammo count [{value}]"))
+        };
+        let counts = if s.contains('=') {
+            s.split(',')
+                .map(|part| {
+                    let (kind, value) = part
+                        .split_once('=')
+                        .ok_or(format!("invalid ammo [{part}], expected <weapon>=<count>"))?;
+                    Ok((kind.parse()?, count(value)?))
+                })
+                .collect::<Result<HashMap<_, _>, String>>()?
+        } else {
+            let value = count(s)?;
+            WEAPONS.iter().map(|w| (*w, value)).collect()
+        };
+        Ok(Ammo {
+            counts: Some(counts),
+        })
+    }
+}
+
+pub(crate) struct ProjectilePlugin;
+impl Plugin for ProjectilePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                next_weapon
+                    .run_if(action_just_pressed(GameAction::NextWeapon))
+                    .in_set(InGameplaySet::Gorillas),
+                split_clusters
+                    .run_if(in_state(Action::Watching))
+                    .in_set(InGameplaySet::Collisions),
+            ),
+        );
+    }
+}
+
+fn next_weapon(player: Res<State<Player>>, mut query: Query<(&Gorilla, &mut Weapon, &Ammo)>) {
+    for (g, mut weapon, ammo) in query.iter_mut() {
+        if &g.player == player.get() {
+            if let Some(next) = weapon.next(ammo) {
+                weapon.0 = next;
+                info!("{:?} picked the {}", g.player, weapon.def().name);
+            }
+        }
+    }
+}
+
+/// cluster bananas come apart once they stop going up
+fn split_clusters(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    query: Query<(Entity, &Banana, &Transform, &Velocity)>,
+) {
+    for (e, banana, transform, velocity) in query.iter() {
+        if !banana.kind.def().splits || velocity.y > 0.0 {
+            continue;
+        }
+        info!("cluster banana split");
+        commands.entity(e).despawn_recursive();
+        for i in 0..CLUSTER_FRAGMENTS {
+            // spread evenly either side of where it was heading
+            let spread = (i as f32 - (CLUSTER_FRAGMENTS - 1) as f32 / 2.0) * CLUSTER_SPREAD;
+            spawn_banana(
+                &asset_server,
+                banana.thrown_by.clone(),
+                &mut commands,
+                transform.translation.truncate(),
+                velocity.0 + Vec2::new(spread, 0.0),
+                ProjectileKind::Fragment,
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cluster_splits_once_it_stops_going_up() {
+        let mut app = App::new();
+        app.add_plugins((MinimalPlugins, AssetPlugin::default()))
+            .init_asset::<Image>()
+            .add_systems(Update, split_clusters);
+        let def = ProjectileKind::Cluster.def();
+        let cluster = app
+            .world
+            .spawn((
+                Banana {
+                    thrown_by: Player::Two,
+                    kind: ProjectileKind::Cluster,
+                    bounces_left: def.bounces,
+                    pierces_left: def.pierces,
+                },
+                Transform::default(),
+                Velocity(Vec2::new(-20.0, 5.0)),
+            ))
+            .id();
+        app.update();
+        assert!(
+            app.world.get_entity(cluster).is_some(),
+            "split on the way up"
+        );
+
+        app.world.get_mut::<Velocity>(cluster).unwrap().y = -1.0;
+        app.update();
+        assert!(app.world.get_entity(cluster).is_none());
+        let mut fragments = app.world.query::<&Banana>();
+        let fragments: Vec<_> = fragments.iter(&app.world).collect();
+        assert_eq!(fragments.len(), CLUSTER_FRAGMENTS);
+        assert!(fragments
+            .iter()
+            .all(|b| b.kind == ProjectileKind::Fragment && b.thrown_by == Player::Two));
+    }
+
+    #[test]
+    fn one_count_is_every_weapon() {
+        let ammo: Ammo = "3".parse().unwrap();
+        for w in WEAPONS {
+            assert_eq!(ammo.left(w), Some(3));
+        }
+    }
+
+    #[test]
+    fn weapon_counts_leave_out_the_rest() {
+        let ammo: Ammo = "banana=5,drill=1".parse().unwrap();
+        assert_eq!(ammo.left(ProjectileKind::Banana), Some(5));
+        assert_eq!(ammo.left(ProjectileKind::Drill), Some(1));
+        assert_eq!(ammo.left(ProjectileKind::Coconut), Some(0));
+        assert!(Ammo::default().left(ProjectileKind::Coconut).is_none());
+    }
+
+    #[test]
+    fn bad_ammo_is_rejected() {
+        for ammo in [
+            "",
+            "-1",
+            "banana=",
+            "banana=x",
+            "sling=2",
+            "banana=2,coconut",
+        ] {
+            assert!(ammo.parse::<Ammo>().is_err(), "[{ammo}] was allowed");
+        }
+    }
+}
@@ -0,0 +1,314 @@
+use std::path::PathBuf;
+
+use rand::random;
+
+use crate::prelude::*;
+use crate::projectile::Ammo;
+use crate::skyline::SkylineStyle;
+use crate::theme::Theme;
+use crate::turn_timer::TimeoutPolicy;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum MatchMode {
+    #[default]
+    Competitive,
+    Training,
+}
+
+/// How the predicted banana path is drawn while aiming, only used in training
+#[derive(Debug, Clone)]
+pub(crate) struct TrajectoryPreview {
+    pub(crate) seconds: f32,
+    pub(crate) wind: bool,
+    pub(crate) stop_at_collision: bool,
+}
+
+impl Default for TrajectoryPreview {
+    fn default() -> Self {
+        TrajectoryPreview {
+            seconds: 3.0,
+            wind: true,
+            stop_at_collision: true,
+        }
+    }
+}
+
+#[derive(Resource, Debug, Clone)]
+pub(crate) struct MatchRules {
+    pub(crate) mode: MatchMode,
+    pub(crate) trajectory: TrajectoryPreview,
+    /// a hand built level to play instead of a random city
+    pub(crate) level: Option<PathBuf>,
+    pub(crate) skyline: SkylineStyle,
+    /// seed for the random city, pass the same one to play the same city again
+    pub(crate) seed: u64,
+    /// size of the random city, level files set their own
+    pub(crate) arena_width: f32,
+    pub(crate) arena_height: f32,
+    /// how the arena looks, left to the level or the wind when not given
+    pub(crate) theme: Option<Theme>,
+    /// rain and snow particles, turn them off on slow machines
+    pub(crate) weather: bool,
+    /// play sounds, turn them off to run without an audio device
+    pub(crate) audio: bool,
+    /// how many birds, blimps and planes cross the sky
+    pub(crate) obstacles: usize,
+    /// how many power up crates float above the city
+    pub(crate) pickups: usize,
+    /// what each gorilla starts with, as much as they like unless limited
+    pub(crate) ammo: Ammo,
+    /// everyone plans their throw in secret and they all fly at once
+    pub(crate) simultaneous: bool,
+    /// how long each turn can take, as long as they like when not given
+    pub(crate) turn_seconds: Option<f32>,
+    /// what happens to a turn that runs out of time
+    pub(crate) timeout: TimeoutPolicy,
+}
+
+impl Default for MatchRules {
+    fn default() -> Self {
+        MatchRules {
+            mode: MatchMode::default(),
+            trajectory: TrajectoryPreview::default(),
+            level: None,
+            skyline: SkylineStyle::default(),
+            seed: random(),
+            arena_width: ARENA_WIDTH,
+            arena_height: ARENA_HEIGHT,
+            theme: None,
+            weather: true,
+            audio: true,
+            obstacles: 0,
+            pickups: 0,
+            ammo: Ammo::default(),
+            simultaneous: false,
+            turn_seconds: None,
+            timeout: TimeoutPolicy::default(),
+        }
+    }
+}
+
+impl MatchRules {
+    pub(crate) fn show_trajectory(&self) -> bool {
+        self.mode == MatchMode::Training
+    }
+
+    /// parse the command line, anything not recognized is an error
+    ///
+    ///   --training                   show the trajectory preview while aiming
+    ///   --preview-seconds <secs>     how far ahead to predict
+    ///   --preview-no-wind            ignore the wind in the preview
+    ///   --preview-through-buildings  do not stop the preview at the first collision
+    ///   --level <file>               play a level file instead of a random city
+    ///   --skyline <style>            uniform, varied, valley or mountains
+    ///   --seed <number>              seed for the random city
+    ///   --arena <width>x<height>     size of the random city in pixels
+    ///   --theme <theme>              day, dusk, night, rain or snow
+    ///   --no-weather                 no rain or snow particles
+    ///   --no-audio                   no sound, and no audio device needed
+    ///   --obstacles <count>          birds, blimps and planes crossing the sky
+    ///   --pickups <count>            power up crates floating above the city
+    ///   --ammo <count|weapon=count,..> what each gorilla can throw, every weapon or just some
+    ///   --simultaneous               plan throws in secret and throw them all at once
+    ///   --turn-timer <secs>          how long each turn can take
+    ///   --timeout <throw|skip>       throw as aimed or lose the turn when time runs out
+    pub(crate) fn from_args(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut rules = MatchRules::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--training" => rules.mode = MatchMode::Training,
+                "--preview-seconds" => {
+                    let value = args.next().ok_or("--preview-seconds needs a value")?;
+                    rules.trajectory.seconds = value
+                        .parse::<f32>()
+                        .ok()
+                        .filter(|s| *s > 0.0)
+                        .ok_or(format!("invalid --preview-seconds [{value}]"))?;
+                }
+                "--preview-no-wind" => rules.trajectory.wind = false,
+                "--preview-through-buildings" => rules.trajectory.stop_at_collision = false,
+                "--level" => {
+                    let value = args.next().ok_or("--level needs a file")?;
+                    rules.level = Some(PathBuf::from(value));
+                }
+                "--skyline" => {
+                    let value = args.next().ok_or("--skyline needs a style")?;
+                    rules.skyline = value.parse()?;
+                }
+                "--seed" => {
+                    let value = args.next().ok_or("--seed needs a number")?;
+                    rules.seed = value
+                        .parse()
+                        .map_err(|_| format!("invalid --seed [{value}]"))?;
+                }
+                "--arena" => {
+                    let value = args.next().ok_or("--arena needs a size like 1920x720")?;
+                    let (width, height) = parse_arena(&value).ok_or(format!(
+                        "invalid --arena [{value}], the width must be a multiple of {BUILDING_WIDTH} and the height of {BUILDING_BRICK_HEIGHT}"
+                    ))?;
+                    rules.arena_width = width;
+                    rules.arena_height = height;
+                }
+                "--theme" => {
+                    let value = args.next().ok_or("--theme needs a theme")?;
+                    rules.theme = Some(value.parse()?);
+                }
+                "--no-weather" => rules.weather = false,
+                "--no-audio" => rules.audio = false,
+                "--obstacles" => {
+                    let value = args.next().ok_or("--obstacles needs a count")?;
+                    rules.obstacles = value
+                        .parse()
+                        .map_err(|_| format!("invalid --obstacles [{value}]"))?;
+                }
+                "--pickups" => {
+                    let value = args.next().ok_or("--pickups needs a count")?;
+                    rules.pickups = value
+                        .parse()
+                        .map_err(|_| format!("invalid --pickups [{value}]"))?;
+                }
+                "--ammo" => {
+                    let value = args.next().ok_or("--ammo needs a count")?;
+                    rules.ammo = value.parse()?;
+                }
+                "--simultaneous" => rules.simultaneous = true,
+                "--turn-timer" => {
+                    let value = args.next().ok_or("--turn-timer needs a value")?;
+                    rules.turn_seconds = Some(
+                        value
+                            .parse::<f32>()
+                            .ok()
+                            .filter(|s| *s > 0.0)
+                            .ok_or(format!("invalid --turn-timer [{value}]"))?,
+                    );
+                }
+                "--timeout" => {
+                    let value = args.next().ok_or("--timeout needs a policy")?;
+                    rules.timeout = value.parse()?;
+                }
+                _ => return Err(format!("unknown argument [{arg}]")),
+            }
+        }
+        Ok(rules)
+    }
+}
+
+/// `<width>x<height>`, wide enough for at least two of the original buildings
+fn parse_arena(value: &str) -> Option<(f32, f32)> {
+    let (width, height) = value.split_once('x')?;
+    let width = width.parse::<u32>().ok()? as f32;
+    let height = height.parse::<u32>().ok()? as f32;
+    let fits = width >= BUILDING_WIDTH * 2.0
+        && width % BUILDING_WIDTH == 0.0
+        && height > 0.0
+        && height % BUILDING_BRICK_HEIGHT == 0.0;
+    fits.then_some((width, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::projectile::ProjectileKind;
+
+    fn parse(args: &[&str]) -> Result<MatchRules, String> {
+        MatchRules::from_args(args.iter().map(|a| a.to_string()))
+    }
+
+    #[test]
+    fn no_args_is_the_default() {
+        let rules = parse(&[]).unwrap();
+        assert_eq!(rules.mode, MatchMode::Competitive);
+        assert!(rules.level.is_none());
+        assert!(rules.weather && rules.audio && !rules.simultaneous);
+        assert!(rules.turn_seconds.is_none());
+    }
+
+    #[test]
+    fn training_preview() {
+        let rules = parse(&[
+            "--training",
+            "--preview-seconds",
+            "1.5",
+            "--preview-no-wind",
+            "--preview-through-buildings",
+        ])
+        .unwrap();
+        assert!(rules.show_trajectory());
+        assert_eq!(rules.trajectory.seconds, 1.5);
+        assert!(!rules.trajectory.wind);
+        assert!(!rules.trajectory.stop_at_collision);
+    }
+
+    #[test]
+    fn arena() {
+        let rules = parse(&[
+            "--level",
+            "assets/levels/canyon.ron",
+            "--skyline",
+            "valley",
+            "--seed",
+            "42",
+            "--arena",
+            "1920x720",
+            "--theme",
+            "night",
+            "--no-weather",
+        ])
+        .unwrap();
+        assert_eq!(rules.level, Some(PathBuf::from("assets/levels/canyon.ron")));
+        assert_eq!(rules.skyline, SkylineStyle::Valley);
+        assert_eq!(rules.seed, 42);
+        assert_eq!((rules.arena_width, rules.arena_height), (1920.0, 720.0));
+        assert_eq!(rules.theme, Some(Theme::Night));
+        assert!(!rules.weather);
+    }
+
+    #[test]
+    fn match_setup() {
+        let rules = parse(&[
+            "--no-audio",
+            "--obstacles",
+            "3",
+            "--pickups",
+            "2",
+            "--ammo",
+            "banana=4",
+            "--simultaneous",
+            "--turn-timer",
+            "20",
+            "--timeout",
+            "skip",
+        ])
+        .unwrap();
+        assert!(!rules.audio);
+        assert_eq!(rules.obstacles, 3);
+        assert_eq!(rules.pickups, 2);
+        assert_eq!(rules.ammo.left(ProjectileKind::Banana), Some(4));
+        assert!(rules.simultaneous);
+        assert_eq!(rules.turn_seconds, Some(20.0));
+        assert_eq!(rules.timeout, TimeoutPolicy::Skip);
+    }
+
+    #[test]
+    fn bad_args_are_rejected() {
+        for args in [
+            &["--unknown"][..],
+            &["--preview-seconds"],
+            &["--preview-seconds", "0"],
+            &["--skyline", "flat"],
+            &["--seed", "-1"],
+            &["--arena", "100x720"],
+            &["--arena", "1920x721"],
+            &["--arena", "1920"],
+            &["--theme", "fog"],
+            &["--obstacles", "many"],
+            &["--pickups"],
+            &["--ammo", "banana"],
+            &["--turn-timer", "-5"],
+            &["--timeout", "pass"],
+        ] {
+            assert!(parse(args).is_err(), "{args:?} was allowed");
+        }
+    }
+}
@@ -0,0 +1,271 @@
+use std::str::FromStr;
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, RngCore};
+
+use crate::level::{BrickMaterial, BrickPattern, BuildingDef, Rgb};
+use crate::prelude::*;
+
+// in bricks, so that buildings always line up with the brick grid
+const MIN_BRICKS_WIDE: u32 = 4;
+const MAX_BRICKS_WIDE: u32 = 10;
+// how likely each material is in the shaped cities, the uniform city is all brick
+const MATERIALS: [(BrickMaterial, u32); 4] = [
+    (BrickMaterial::Brick, 13),
+    (BrickMaterial::Concrete, 4),
+    (BrickMaterial::Glass, 2),
+    (BrickMaterial::Steel, 1),
+];
+const AWNING_CHANCE: f64 = 0.15;
+
+/// Lays out the buildings of a random city, left to right across the whole arena
+pub(crate) trait SkylineGenerator {
+    fn buildings(
+        &self,
+        rng: &mut StdRng,
+        width: f32,
+        height: f32,
+        colors: &[Rgb],
+    ) -> Vec<BuildingDef>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum SkylineStyle {
+    /// the original, equal width buildings of any height
+    #[default]
+    Uniform,
+    Varied,
+    /// short buildings in the middle
+    Valley,
+    /// tall buildings in the middle
+    Mountains,
+}
+
+impl SkylineStyle {
+    pub(crate) fn generator(&self) -> Box<dyn SkylineGenerator> {
+        match self {
+            SkylineStyle::Uniform => Box::new(Uniform),
+            SkylineStyle::Varied => Box::new(Varied),
+            SkylineStyle::Valley => Box::new(Valley),
+            SkylineStyle::Mountains => Box::new(Mountains),
+        }
+    }
+}
+
+impl FromStr for SkylineStyle {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "uniform" => Ok(SkylineStyle::Uniform),
+            "varied" => Ok(SkylineStyle::Varied),
+            "valley" => Ok(SkylineStyle::Valley),
+            "mountains" => Ok(SkylineStyle::Mountains),
+            _ => Err(format!(
+                "unknown skyline [{s}], expected uniform, varied, valley or mountains"
+            )),
+        }
+    }
+}
+
+struct Uniform;
+impl SkylineGenerator for Uniform {
+    fn buildings(
+        &self,
+        rng: &mut StdRng,
+        width: f32,
+        height: f32,
+        colors: &[Rgb],
+    ) -> Vec<BuildingDef> {
+        let num_buildings = (width / BUILDING_WIDTH).round() as i32;
+        assert_eq!(num_buildings as f32 * BUILDING_WIDTH, width);
+
+        (0..num_buildings)
+            .map(|i| {
+                let h = rng.next_u32() as f32 % (height / 2.0) + height / 8.0;
+                building(rng, BUILDING_WIDTH * i as f32, BUILDING_WIDTH, h, colors)
+            })
+            .collect()
+    }
+}
+
+struct Varied;
+impl SkylineGenerator for Varied {
+    fn buildings(
+        &self,
+        rng: &mut StdRng,
+        width: f32,
+        height: f32,
+        colors: &[Rgb],
+    ) -> Vec<BuildingDef> {
+        // no shape, every building can be anywhere from short to tall
+        shaped(rng, width, height, colors, |_| 1.0, 0.0)
+    }
+}
+
+struct Valley;
+impl SkylineGenerator for Valley {
+    fn buildings(
+        &self,
+        rng: &mut StdRng,
+        width: f32,
+        height: f32,
+        colors: &[Rgb],
+    ) -> Vec<BuildingDef> {
+        shaped(
+            rng,
+            width,
+            height,
+            colors,
+            |center| (2.0 * center - 1.0).abs(),
+            0.6,
+        )
+    }
+}
+
+struct Mountains;
+impl SkylineGenerator for Mountains {
+    fn buildings(
+        &self,
+        rng: &mut StdRng,
+        width: f32,
+        height: f32,
+        colors: &[Rgb],
+    ) -> Vec<BuildingDef> {
+        shaped(
+            rng,
+            width,
+            height,
+            colors,
+            |center| 1.0 - (2.0 * center - 1.0).abs(),
+            0.6,
+        )
+    }
+}
+
+/// Buildings of varied widths whose heights follow `shape`, which maps the center of a
+/// building from 0.0 (left) to 1.0 (right) to how tall it can be from 0.0 to 1.0.
+/// Each building is then randomly between `least` and all of that height.
+fn shaped(
+    rng: &mut StdRng,
+    arena_width: f32,
+    arena_height: f32,
+    colors: &[Rgb],
+    shape: impl Fn(f32) -> f32,
+    least: f32,
+) -> Vec<BuildingDef> {
+    let mut buildings = vec![];
+    let mut x = 0.0;
+    for width in varied_widths(rng, arena_width) {
+        let center = (x + width / 2.0) / arena_width;
+        let tallest = arena_height / 2.0 * shape(center);
+        let height = arena_height / 8.0 + tallest * rng.gen_range(least..=1.0);
+        let mut b = building(rng, x, width, height, colors);
+        b.material = MATERIALS
+            .choose_weighted(rng, |(_, weight)| *weight)
+            .map_or(BrickMaterial::Brick, |(material, _)| *material);
+        b.awning = rng.gen_bool(AWNING_CHANCE);
+        buildings.push(b);
+        x += width;
+    }
+    buildings
+}
+
+fn varied_widths(rng: &mut StdRng, arena_width: f32) -> Vec<f32> {
+    let total_bricks = (arena_width / BUILDING_BRICK_WIDTH) as u32;
+    let mut widths = vec![];
+    let mut used = 0;
+    while total_bricks - used >= MIN_BRICKS_WIDE * 2 {
+        let bricks = rng
+            .gen_range(MIN_BRICKS_WIDE..=MAX_BRICKS_WIDE)
+            .min(total_bricks - used - MIN_BRICKS_WIDE);
+        widths.push(bricks);
+        used += bricks;
+    }
+    // whatever is left over is the last building
+    widths.push(total_bricks - used);
+    widths
+        .into_iter()
+        .map(|bricks| bricks as f32 * BUILDING_BRICK_WIDTH)
+        .collect()
+}
+
+fn building(rng: &mut StdRng, x: f32, width: f32, height: f32, colors: &[Rgb]) -> BuildingDef {
+    BuildingDef {
+        x,
+        width,
+        height: f32::round(height / BUILDING_BRICK_HEIGHT) * BUILDING_BRICK_HEIGHT,
+        color: *colors.choose(rng).unwrap_or(&Rgb(0, 0, 0)),
+        bricks: BrickPattern::Solid,
+        windows: true,
+        material: BrickMaterial::Brick,
+        awning: false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::SeedableRng;
+
+    use super::*;
+
+    const STYLES: [SkylineStyle; 4] = [
+        SkylineStyle::Uniform,
+        SkylineStyle::Varied,
+        SkylineStyle::Valley,
+        SkylineStyle::Mountains,
+    ];
+    const COLORS: [Rgb; 2] = [Rgb(174, 177, 166), Rgb(98, 88, 81)];
+
+    fn city(style: SkylineStyle, seed: u64, width: f32) -> Vec<BuildingDef> {
+        let mut rng = StdRng::seed_from_u64(seed);
+        style
+            .generator()
+            .buildings(&mut rng, width, ARENA_HEIGHT, &COLORS)
+    }
+
+    #[test]
+    fn same_seed_same_skyline() {
+        for style in STYLES {
+            let first = city(style, 7, ARENA_WIDTH);
+            let again = city(style, 7, ARENA_WIDTH);
+            assert_eq!(first.len(), again.len(), "{style:?}");
+            for (a, b) in first.iter().zip(again.iter()) {
+                assert_eq!(
+                    (a.x, a.width, a.height),
+                    (b.x, b.width, b.height),
+                    "{style:?}"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn buildings_fill_the_arena() {
+        for style in STYLES {
+            for (seed, width) in [(1, ARENA_WIDTH), (2, ARENA_WIDTH * 2.0), (3, 320.0)] {
+                let buildings = city(style, seed, width);
+                let mut x = 0.0;
+                for b in &buildings {
+                    assert_eq!(b.x, x, "{style:?} has a gap");
+                    assert_eq!(b.width % BUILDING_BRICK_WIDTH, 0.0, "{style:?}");
+                    x += b.width;
+                }
+                assert_eq!(x, width, "{style:?} with seed {seed}");
+            }
+        }
+    }
+
+    #[test]
+    fn varied_widths_sum_to_the_arena() {
+        for seed in 0..50 {
+            let mut rng = StdRng::seed_from_u64(seed);
+            let widths = varied_widths(&mut rng, ARENA_WIDTH);
+            assert_eq!(widths.iter().sum::<f32>(), ARENA_WIDTH);
+            assert!(widths
+                .iter()
+                .all(|w| *w >= MIN_BRICKS_WIDE as f32 * BUILDING_BRICK_WIDTH));
+        }
+    }
+}
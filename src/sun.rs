@@ -0,0 +1,181 @@
+use std::f32::consts::PI;
+
+use crate::game::InGameplaySet;
+use crate::level::Level;
+use crate::prelude::*;
+use crate::theme::Theme;
+
+// how long the sun looks surprised after a banana goes through it
+const SURPRISED_SECONDS: f32 = 3.0;
+const SUN_RAYS: usize = 12;
+
+/// The sun, bananas fly straight through it but it does not like it
+#[derive(Component)]
+pub(crate) struct Sun {
+    surprised: Timer,
+    /// the banana last seen inside the sun, so each one only counts once
+    hit_by: Option<Entity>,
+}
+
+/// One of the sun's mouths, only the one matching its mood is shown
+#[derive(Component)]
+struct SunMouth {
+    surprised: bool,
+}
+
+#[derive(Event, Debug)]
+pub(crate) struct SunHitEvent {
+    pub(crate) thrown_by: Player,
+}
+
+pub(crate) struct SunPlugin;
+impl Plugin for SunPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SunHitEvent>()
+            .add_systems(Startup, setup_sun)
+            .add_systems(
+                Update,
+                (check_banana_hits_sun, update_sun_face)
+                    .chain()
+                    .in_set(InGameplaySet::Collisions),
+            );
+    }
+}
+
+fn setup_sun(mut commands: Commands, level: Res<Level>, theme: Res<Theme>) {
+    if !theme.palette().sun {
+        return;
+    }
+    let center = Vec2::new(0.0, level.height / 2.0 - SUN_TOP_MARGIN);
+    let mut surprised = Timer::from_seconds(SURPRISED_SECONDS, TimerMode::Once);
+    // start out smiling
+    surprised.tick(surprised.duration());
+
+    let mut rays = GeometryBuilder::new();
+    for i in 0..SUN_RAYS {
+        let direction = Vec2::from_angle(i as f32 * 2.0 * PI / SUN_RAYS as f32);
+        rays = rays.add(&shapes::Line(
+            direction * SUN_RADIUS,
+            direction * (SUN_RADIUS + SUN_RAY_LENGTH),
+        ));
+    }
+
+    commands
+        .spawn((
+            Sun {
+                surprised,
+                hit_by: None,
+            },
+            ShapeBundle {
+                path: rays
+                    .add(&shapes::Circle {
+                        radius: SUN_RADIUS,
+                        center: Vec2::ZERO,
+                    })
+                    .build(),
+                spatial: SpatialBundle::from_transform(Transform::from_translation(
+                    center.extend(SUN_Z_INDEX),
+                )),
+                ..default()
+            },
+            Fill::color(SUN_COLOR),
+            Stroke::new(SUN_COLOR, 3.0),
+        ))
+        .with_children(|parent| {
+            // the face sits just in front of the sun
+            for x in [-SUN_RADIUS / 3.0, SUN_RADIUS / 3.0] {
+                parent.spawn(face_part(
+                    &shapes::Circle {
+                        radius: 3.0,
+                        center: Vec2::new(x, SUN_RADIUS / 4.0),
+                    },
+                    Visibility::Inherited,
+                ));
+            }
+            parent.spawn((
+                SunMouth { surprised: false },
+                face_part(
+                    &shapes::SvgPathShape {
+                        svg_doc_size_in_px: Vec2::new(SUN_RADIUS, SUN_RADIUS),
+                        svg_path_string: "M 5 20 Q 15 30 25 20 Q 15 26 5 20".to_string(),
+                    },
+                    Visibility::Inherited,
+                ),
+            ));
+            parent.spawn((
+                SunMouth { surprised: true },
+                face_part(
+                    &shapes::Circle {
+                        radius: 6.0,
+                        center: Vec2::new(0.0, -SUN_RADIUS / 3.0),
+                    },
+                    Visibility::Hidden,
+                ),
+            ));
+        });
+}
+
+fn face_part(shape: &impl Geometry, visibility: Visibility) -> (ShapeBundle, Fill) {
+    (
+        ShapeBundle {
+            path: GeometryBuilder::build_as(shape),
+            spatial: SpatialBundle {
+                visibility,
+                transform: Transform::from_xyz(0.0, 0.0, 0.01),
+                ..default()
+            },
+            ..default()
+        },
+        Fill::color(Color::BLACK),
+    )
+}
+
+fn check_banana_hits_sun(
+    mut sun_query: Query<(&mut Sun, &Transform)>,
+    banana_query: Query<(Entity, &Banana, &Transform)>,
+    mut sun_hit_events: EventWriter<SunHitEvent>,
+) {
+    for (mut sun, sun_transform) in sun_query.iter_mut() {
+        let center = sun_transform.translation.truncate();
+        for (banana_entity, banana, banana_transform) in banana_query.iter() {
+            let inside = banana_transform.translation.truncate().distance(center) < SUN_RADIUS;
+            if inside && sun.hit_by != Some(banana_entity) {
+                sun.hit_by = Some(banana_entity);
+                sun_hit_events.send(SunHitEvent {
+                    thrown_by: banana.thrown_by.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn update_sun_face(
+    time: Res<Time>,
+    mut sun_hit_events: EventReader<SunHitEvent>,
+    mut sun_query: Query<&mut Sun>,
+    mut mouth_query: Query<(&SunMouth, &mut Visibility)>,
+) {
+    let mut hit = false;
+    for event in sun_hit_events.read() {
+        info!(
+            "banana thrown by {:?} went through the sun",
+            event.thrown_by
+        );
+        hit = true;
+    }
+    for mut sun in sun_query.iter_mut() {
+        if hit {
+            sun.surprised.reset();
+        }
+        sun.surprised.tick(time.delta());
+        let surprised = !sun.surprised.finished();
+        for (mouth, mut visibility) in mouth_query.iter_mut() {
+            let wanted = if mouth.surprised == surprised {
+                Visibility::Inherited
+            } else {
+                Visibility::Hidden
+            };
+            visibility.set_if_neq(wanted);
+        }
+    }
+}
@@ -0,0 +1,152 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::level::Rgb;
+use crate::prelude::*;
+
+// wind at least this strong brings in the weather when the theme is left to chance
+const STORM_WIND: i32 = 14;
+
+/// How the arena looks, chosen once per match
+#[derive(Resource, Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) enum Theme {
+    #[default]
+    Day,
+    Dusk,
+    /// dark sky and most of the windows lit
+    Night,
+    Rain,
+    Snow,
+}
+
+/// Colors for everything a theme changes
+pub(crate) struct Palette {
+    pub(crate) sky: Color,
+    /// building colors for random cities, level files pick their own
+    pub(crate) buildings: [Rgb; 3],
+    pub(crate) text: Color,
+    pub(crate) wind_outline: Color,
+    pub(crate) wind_fill: Color,
+    pub(crate) lit_window_chance: f64,
+    pub(crate) sun: bool,
+}
+
+/// What falls from the sky
+pub(crate) struct Weather {
+    pub(crate) color: Color,
+    pub(crate) size: Vec2,
+    /// in pixels per second
+    pub(crate) fall_speed: f32,
+    /// sideways pixels per second for each unit of wind, lighter things blow further
+    pub(crate) wind_drift: f32,
+}
+
+impl Theme {
+    /// a theme to go with the wind, storms bring rain or snow and calm days can be any time of day
+    pub(crate) fn for_wind(wind: i32, seed: u64) -> Theme {
+        let themes: &[Theme] = if wind.abs() >= STORM_WIND {
+            &[Theme::Rain, Theme::Snow]
+        } else {
+            &[Theme::Day, Theme::Dusk, Theme::Night]
+        };
+        themes[(seed % themes.len() as u64) as usize]
+    }
+
+    pub(crate) fn palette(&self) -> Palette {
+        match self {
+            Theme::Day => Palette {
+                sky: Color::rgb_u8(126, 161, 219),
+                buildings: [Rgb(174, 177, 166), Rgb(98, 88, 81), Rgb(208, 208, 181)],
+                text: Color::BLACK,
+                wind_outline: Color::DARK_GRAY,
+                wind_fill: Color::GRAY,
+                lit_window_chance: LIT_WINDOW_CHANCE,
+                sun: true,
+            },
+            Theme::Dusk => Palette {
+                sky: Color::rgb_u8(238, 140, 92),
+                buildings: [Rgb(150, 120, 130), Rgb(90, 70, 80), Rgb(190, 150, 140)],
+                text: Color::BLACK,
+                wind_outline: Color::rgb_u8(90, 50, 60),
+                wind_fill: Color::rgb_u8(150, 90, 90),
+                lit_window_chance: 0.5,
+                sun: true,
+            },
+            Theme::Night => Palette {
+                sky: Color::rgb_u8(16, 22, 48),
+                buildings: [Rgb(70, 72, 90), Rgb(45, 45, 60), Rgb(95, 95, 110)],
+                text: Color::WHITE,
+                wind_outline: Color::SILVER,
+                wind_fill: Color::GRAY,
+                lit_window_chance: 0.75,
+                sun: false,
+            },
+            Theme::Rain => Palette {
+                sky: Color::rgb_u8(110, 118, 128),
+                buildings: [Rgb(120, 125, 125), Rgb(75, 70, 68), Rgb(150, 150, 140)],
+                text: Color::BLACK,
+                wind_outline: Color::DARK_GRAY,
+                wind_fill: Color::GRAY,
+                lit_window_chance: 0.5,
+                sun: false,
+            },
+            Theme::Snow => Palette {
+                sky: Color::rgb_u8(200, 210, 222),
+                buildings: [Rgb(160, 165, 170), Rgb(110, 100, 95), Rgb(200, 200, 190)],
+                text: Color::BLACK,
+                wind_outline: Color::DARK_GRAY,
+                wind_fill: Color::GRAY,
+                lit_window_chance: LIT_WINDOW_CHANCE,
+                sun: false,
+            },
+        }
+    }
+
+    pub(crate) fn weather(&self) -> Option<Weather> {
+        match self {
+            Theme::Rain => Some(Weather {
+                color: Color::rgba(0.75, 0.8, 0.9, 0.6),
+                size: Vec2::new(1.0, 10.0),
+                fall_speed: 600.0,
+                wind_drift: 6.0,
+            }),
+            Theme::Snow => Some(Weather {
+                color: Color::rgba(1.0, 1.0, 1.0, 0.9),
+                size: Vec2::new(3.0, 3.0),
+                fall_speed: 60.0,
+                wind_drift: 10.0,
+            }),
+            _ => None,
+        }
+    }
+}
+
+impl FromStr for Theme {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "day" => Ok(Theme::Day),
+            "dusk" => Ok(Theme::Dusk),
+            "night" => Ok(Theme::Night),
+            "rain" => Ok(Theme::Rain),
+            "snow" => Ok(Theme::Snow),
+            _ => Err(format!(
+                "unknown theme [{s}], expected day, dusk, night, rain or snow"
+            )),
+        }
+    }
+}
+
+pub(crate) struct ThemePlugin;
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, setup_sky);
+    }
+}
+
+fn setup_sky(mut commands: Commands, theme: Res<Theme>) {
+    info!("theme is {:?}", *theme);
+    commands.insert_resource(ClearColor(theme.palette().sky));
+}
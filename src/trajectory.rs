@@ -0,0 +1,132 @@
+#![allow(clippy::type_complexity, clippy::too_many_arguments)]
+
+use bevy::math::bounding::{Aabb2d, IntersectsVolume};
+
+use crate::game::{cleanup_system, Action, AngleSpeed, InGameplaySet};
+use crate::level::Level;
+use crate::pickups::{Crate, PowerUps};
+use crate::prelude::*;
+use crate::projectile::Weapon;
+use crate::rules::MatchRules;
+
+// only draw every few physics steps so that the path looks dotted
+const STEPS_PER_DOT: usize = 4;
+
+#[derive(Component)]
+struct TrajectoryDot;
+
+pub(crate) struct TrajectoryPlugin;
+impl Plugin for TrajectoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            update_trajectory
+                .run_if(|rules: Res<MatchRules>| rules.show_trajectory())
+                // it would give away the plan
+                .run_if(in_state(Action::Enter))
+                .in_set(InGameplaySet::Gorillas),
+        )
+        .add_systems(OnExit(Action::Enter), cleanup_system::<TrajectoryDot>);
+    }
+}
+
+fn update_trajectory(
+    mut commands: Commands,
+    rules: Res<MatchRules>,
+    level: Res<Level>,
+    fixed_time: Res<Time<Fixed>>,
+    player: Res<State<Player>>,
+    gorilla_query: Query<(&Gorilla, &Transform, &AngleSpeed, &Weapon, &PowerUps)>,
+    gravity_query: Query<&GlobalWorldAcceleration, With<Gravity>>,
+    wind_query: Query<&GlobalWorldAcceleration, Without<Gravity>>,
+    collider_query: Query<&Transform, (With<Collider>, Without<Crate>)>,
+    mut dot_query: Query<
+        (&mut Transform, &mut Visibility),
+        (With<TrajectoryDot>, Without<Collider>, Without<Gorilla>),
+    >,
+) {
+    let Some((_, t, a, weapon, power_ups)) = gorilla_query
+        .iter()
+        .find(|(g, _, _, _, _)| &g.player == player.get())
+    else {
+        return;
+    };
+
+    let dt = fixed_time.timestep().as_secs_f32();
+    let steps = (rules.trajectory.seconds / dt).ceil() as usize;
+    let gravity = gravity_query.iter().map(|acc| acc.0).sum();
+    let wind = if rules.trajectory.wind {
+        wind_query.iter().map(|acc| acc.0).sum()
+    } else {
+        Vec2::ZERO
+    };
+    // what the banana's `WindFactor` will be once it is thrown
+    let wind_factor = weapon.def().wind_factor * power_ups.wind_factor();
+    let start = t.translation.truncate();
+    let path = simulate_throw(
+        start,
+        a.angle,
+        a.speed,
+        player.get(),
+        wind,
+        wind_factor,
+        gravity,
+        dt,
+    );
+
+    let mut dots = Vec::with_capacity(steps / STEPS_PER_DOT + 1);
+    for (step, position) in path.take(steps).enumerate() {
+        if is_off_screen(position, &level) {
+            break;
+        }
+        // collisions are only checked once the banana is being watched
+        if rules.trajectory.stop_at_collision
+            && position.distance(start) > BANANA_WATCH_DISTANCE
+            && did_collide(&collider_query, position)
+        {
+            dots.push(position);
+            break;
+        }
+        if (step + 1) % STEPS_PER_DOT == 0 {
+            dots.push(position);
+        }
+    }
+
+    // reuse the dots we already have, spawn any missing and hide the rest
+    let mut existing = dot_query.iter_mut();
+    for dot in dots {
+        if let Some((mut dot_transform, mut visibility)) = existing.next() {
+            dot_transform.translation = dot.extend(TRAJECTORY_Z_INDEX);
+            *visibility = Visibility::Inherited;
+        } else {
+            commands.spawn((
+                TrajectoryDot,
+                SpriteBundle {
+                    transform: Transform::from_translation(dot.extend(TRAJECTORY_Z_INDEX)),
+                    sprite: Sprite {
+                        color: *Color::ORANGE.clone().set_a(0.7),
+                        custom_size: Some(Vec2::splat(TRAJECTORY_DOT_SIZE)),
+                        ..default()
+                    },
+                    ..default()
+                },
+            ));
+        }
+    }
+    for (_, mut visibility) in existing {
+        *visibility = Visibility::Hidden;
+    }
+}
+
+fn did_collide(
+    collider_query: &Query<&Transform, (With<Collider>, Without<Crate>)>,
+    position: Vec2,
+) -> bool {
+    let banana = Aabb2d::new(position, BANANA_SCALE / 2.0);
+    collider_query.iter().any(|t| {
+        banana.intersects(&Aabb2d::new(
+            t.translation.truncate(),
+            t.scale.truncate() / 2.0,
+        ))
+    })
+}
@@ -0,0 +1,245 @@
+use std::str::FromStr;
+
+use crate::defense::{TurnAction, TurnSpentEvent};
+use crate::game::{Action, InGameplaySet};
+use crate::prelude::*;
+use crate::rules::MatchRules;
+
+/// What happens to a turn that runs out of time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) enum TimeoutPolicy {
+    /// throw with whatever angle and speed they have set
+    #[default]
+    Throw,
+    /// lose the turn without throwing
+    Skip,
+}
+
+impl FromStr for TimeoutPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "throw" => Ok(TimeoutPolicy::Throw),
+            "skip" => Ok(TimeoutPolicy::Skip),
+            _ => Err(format!("unknown timeout [{s}], expected throw or skip")),
+        }
+    }
+}
+
+/// How long the current player has left to take their turn, only running when the
+/// match has a turn timer
+#[derive(Resource, Default)]
+pub(crate) struct TurnClock {
+    timer: Option<Timer>,
+    warned: bool,
+}
+
+impl TurnClock {
+    /// whole seconds left, rounded up so it never shows 0 while there is still time
+    pub(crate) fn seconds_left(&self) -> Option<u32> {
+        self.timer
+            .as_ref()
+            .map(|t| t.remaining_secs().ceil() as u32)
+    }
+
+    fn expired(&self) -> bool {
+        self.timer.as_ref().is_some_and(|t| t.finished())
+    }
+}
+
+/// run condition for anything that can only be done while there is time left
+pub(crate) fn turn_time_left(clock: Res<TurnClock>) -> bool {
+    !clock.expired()
+}
+
+/// The turn is nearly out of time
+#[derive(Event)]
+pub(crate) struct TurnTimerWarningEvent;
+
+pub(crate) struct TurnTimerPlugin;
+impl Plugin for TurnTimerPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TurnClock>()
+            .add_event::<TurnTimerWarningEvent>()
+            .add_systems(OnEnter(Action::Enter), start_clock)
+            .add_systems(OnEnter(Action::Planning), start_clock)
+            .add_systems(
+                Update,
+                (
+                    // players skipped or handing over their plan get a fresh clock
+                    start_clock.run_if(state_changed::<Player>),
+                    run_clock,
+                )
+                    .chain()
+                    .in_set(InGameplaySet::Gorillas),
+            );
+    }
+}
+
+fn start_clock(rules: Res<MatchRules>, mut clock: ResMut<TurnClock>) {
+    *clock = TurnClock {
+        timer: rules
+            .turn_seconds
+            .map(|secs| Timer::from_seconds(secs, TimerMode::Once)),
+        warned: false,
+    };
+}
+
+#[allow(clippy::too_many_arguments)]
+fn run_clock(
+    time: Res<Time>,
+    rules: Res<MatchRules>,
+    action: Res<State<Action>>,
+    mut clock: ResMut<TurnClock>,
+    mut next_action: ResMut<NextState<Action>>,
+    mut next_turn_action: ResMut<NextState<TurnAction>>,
+    mut throw_events: EventWriter<ThrowBananaEvent>,
+    mut turn_spent: EventWriter<TurnSpentEvent>,
+    mut warning_events: EventWriter<TurnTimerWarningEvent>,
+) {
+    let TurnClock { timer, warned } = &mut *clock;
+    let Some(timer) = timer else {
+        return;
+    };
+    timer.tick(time.delta());
+    if timer.just_finished() {
+        info!("out of time, {:?}", rules.timeout);
+    }
+    if timer.finished() {
+        match (rules.timeout, action.get()) {
+            // a plan can only be locked in, skipping would hold up everyone else. Only
+            // ask once, asking again could lock in the next player's plan too
+            (_, Action::Planning) if timer.just_finished() => {
+                throw_events.send(ThrowBananaEvent);
+            }
+            (_, Action::Planning) => {}
+            // keep asking, the throw waits for a gorilla in the air to land
+            (TimeoutPolicy::Throw, _) => {
+                next_turn_action.set(TurnAction::Throw);
+                throw_events.send(ThrowBananaEvent);
+            }
+            (TimeoutPolicy::Skip, _) if timer.just_finished() => {
+                turn_spent.send(TurnSpentEvent);
+                next_action.set(Action::Watching);
+            }
+            (TimeoutPolicy::Skip, _) => {}
+        }
+    } else if !*warned && timer.remaining_secs() <= TURN_WARNING_SECONDS {
+        *warned = true;
+        warning_events.send(TurnTimerWarningEvent);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    const TURN_SECONDS: f32 = 10.0;
+
+    fn app(timeout: TimeoutPolicy, action: Action) -> App {
+        let mut app = App::new();
+        app.insert_resource(MatchRules {
+            turn_seconds: Some(TURN_SECONDS),
+            timeout,
+            ..default()
+        })
+        .insert_resource(Time::<()>::default())
+        .init_resource::<TurnClock>()
+        .insert_state(action)
+        .init_state::<TurnAction>()
+        .add_event::<ThrowBananaEvent>()
+        .add_event::<TurnSpentEvent>()
+        .add_event::<TurnTimerWarningEvent>()
+        .add_systems(Startup, start_clock)
+        .add_systems(Update, run_clock);
+        app
+    }
+
+    /// runs a frame `seconds` long
+    fn frame(app: &mut App, seconds: f32) {
+        app.world
+            .resource_mut::<Time>()
+            .advance_by(Duration::from_secs_f32(seconds));
+        app.update();
+    }
+
+    fn sent<E: Event>(app: &mut App) -> usize {
+        app.world.resource_mut::<Events<E>>().drain().count()
+    }
+
+    #[test]
+    fn warns_once_before_running_out() {
+        let mut app = app(TimeoutPolicy::Throw, Action::Enter);
+        frame(&mut app, TURN_SECONDS - TURN_WARNING_SECONDS - 1.0);
+        assert_eq!(sent::<TurnTimerWarningEvent>(&mut app), 0);
+        frame(&mut app, 2.0);
+        frame(&mut app, 1.0);
+        assert_eq!(sent::<TurnTimerWarningEvent>(&mut app), 1);
+        assert_eq!(sent::<ThrowBananaEvent>(&mut app), 0);
+    }
+
+    #[test]
+    fn throw_keeps_asking_until_it_goes() {
+        let mut app = app(TimeoutPolicy::Throw, Action::Enter);
+        app.world
+            .resource_mut::<NextState<TurnAction>>()
+            .set(TurnAction::Wall);
+        frame(&mut app, TURN_SECONDS);
+        assert_eq!(sent::<ThrowBananaEvent>(&mut app), 1);
+        // a gorilla in the air holds the throw up
+        frame(&mut app, 0.1);
+        frame(&mut app, 0.1);
+        assert_eq!(sent::<ThrowBananaEvent>(&mut app), 2);
+        assert_eq!(
+            app.world.resource::<State<TurnAction>>().get(),
+            &TurnAction::Throw
+        );
+        assert_eq!(sent::<TurnSpentEvent>(&mut app), 0);
+    }
+
+    #[test]
+    fn skip_spends_the_turn_once() {
+        let mut app = app(TimeoutPolicy::Skip, Action::Enter);
+        frame(&mut app, TURN_SECONDS);
+        assert_eq!(sent::<TurnSpentEvent>(&mut app), 1);
+        frame(&mut app, 0.1);
+        frame(&mut app, 0.1);
+        assert_eq!(sent::<TurnSpentEvent>(&mut app), 0);
+        assert_eq!(sent::<ThrowBananaEvent>(&mut app), 0);
+        assert_eq!(
+            app.world.resource::<State<Action>>().get(),
+            &Action::Watching
+        );
+    }
+
+    #[test]
+    fn planning_locks_in_once() {
+        for timeout in [TimeoutPolicy::Throw, TimeoutPolicy::Skip] {
+            let mut app = app(timeout, Action::Planning);
+            frame(&mut app, TURN_SECONDS);
+            assert_eq!(sent::<ThrowBananaEvent>(&mut app), 1, "{timeout:?}");
+            frame(&mut app, 0.1);
+            frame(&mut app, 0.1);
+            assert_eq!(sent::<ThrowBananaEvent>(&mut app), 0, "{timeout:?}");
+            assert_eq!(sent::<TurnSpentEvent>(&mut app), 0, "{timeout:?}");
+            assert_eq!(
+                app.world.resource::<State<Action>>().get(),
+                &Action::Planning,
+                "{timeout:?}"
+            );
+        }
+    }
+
+    #[test]
+    fn no_clock_without_a_turn_limit() {
+        let mut app = app(TimeoutPolicy::Throw, Action::Enter);
+        app.world.resource_mut::<MatchRules>().turn_seconds = None;
+        app.world.run_schedule(Startup);
+        frame(&mut app, TURN_SECONDS * 10.0);
+        assert_eq!(app.world.resource::<TurnClock>().seconds_left(), None);
+        assert_eq!(sent::<ThrowBananaEvent>(&mut app), 0);
+    }
+}
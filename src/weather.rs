@@ -0,0 +1,248 @@
+use rand::{thread_rng, Rng};
+
+use crate::level::Level;
+use crate::prelude::*;
+use crate::rules::MatchRules;
+use crate::theme::{Theme, Weather};
+
+// frames slower than this on average make the weather back off
+const SLOW_FRAME_SECONDS: f32 = 1.0 / 30.0;
+// and faster than this lets it come back
+const FAST_FRAME_SECONDS: f32 = 1.0 / 45.0;
+// the first frames are slow while everything loads, they say nothing about the machine
+const WARMUP_FRAMES: u32 = 10;
+// how much each frame counts towards the average, so a single hitch is ignored
+const FRAME_AVERAGE_WEIGHT: f32 = 0.05;
+// new particles per frame, so they drift in rather than appear all at once
+const SPAWN_PER_FRAME: usize = 10;
+// how quickly the particles pick up a change in the wind, per second
+const DRIFT_EASING: f32 = 2.0;
+
+/// A rain drop or snow flake, purely for show, it never collides with anything
+#[derive(Component)]
+struct WeatherParticle {
+    fall_speed: f32,
+}
+
+/// How many particles there can be at once, shrinks when frames get slow and grows
+/// back to `max` when they are fast again
+#[derive(Resource)]
+struct WeatherBudget {
+    particles: usize,
+    max: usize,
+}
+
+impl WeatherBudget {
+    fn adjust(&mut self, average_frame: f32) {
+        if average_frame > SLOW_FRAME_SECONDS && self.particles > 0 {
+            self.particles -= self.particles / 10 + 1;
+            debug!("slow frames, weather budget down to {}", self.particles);
+        } else if average_frame < FAST_FRAME_SECONDS && self.particles < self.max {
+            self.particles = (self.particles + self.max / 20 + 1).min(self.max);
+            debug!("fast frames, weather budget up to {}", self.particles);
+        }
+    }
+}
+
+/// Running average of how long frames take, once the game has warmed up
+#[derive(Default)]
+struct FrameAverage {
+    frames: u32,
+    seconds: Option<f32>,
+}
+
+impl FrameAverage {
+    /// the average including this frame, `None` while still warming up
+    fn add(&mut self, seconds: f32) -> Option<f32> {
+        self.frames += 1;
+        if self.frames <= WARMUP_FRAMES {
+            return None;
+        }
+        let average = self.seconds.map_or(seconds, |average| {
+            average + (seconds - average) * FRAME_AVERAGE_WEIGHT
+        });
+        self.seconds = Some(average);
+        self.seconds
+    }
+}
+
+/// The sideways speed of the weather, catching up with the wind
+#[derive(Resource, Default)]
+struct WeatherDrift(f32);
+
+pub(crate) struct WeatherPlugin;
+impl Plugin for WeatherPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<WeatherDrift>()
+            .add_systems(Startup, setup_weather)
+            .add_systems(
+                Update,
+                (keep_to_budget, spawn_particles, blow_and_fall)
+                    .chain()
+                    .run_if(resource_exists::<WeatherBudget>),
+            );
+    }
+}
+
+fn setup_weather(
+    mut commands: Commands,
+    rules: Res<MatchRules>,
+    theme: Res<Theme>,
+    level: Res<Level>,
+) {
+    if !rules.weather || theme.weather().is_none() {
+        return;
+    }
+    // as dense in a big arena as in a small one, up to the budget
+    let area = (level.width * level.height) / (VIEW_WIDTH * VIEW_HEIGHT);
+    let particles = ((WEATHER_PARTICLES as f32 * area) as usize).min(MAX_WEATHER_PARTICLES);
+    info!("weather budget of {particles} particles");
+    commands.insert_resource(WeatherBudget {
+        particles,
+        max: particles,
+    });
+}
+
+fn keep_to_budget(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut budget: ResMut<WeatherBudget>,
+    mut average_frame: Local<FrameAverage>,
+    query: Query<Entity, With<WeatherParticle>>,
+) {
+    if let Some(average) = average_frame.add(time.delta_seconds()) {
+        budget.adjust(average);
+    }
+    for e in query.iter().skip(budget.particles) {
+        commands.entity(e).despawn();
+    }
+}
+
+fn spawn_particles(
+    mut commands: Commands,
+    theme: Res<Theme>,
+    level: Res<Level>,
+    budget: Res<WeatherBudget>,
+    query: Query<(), With<WeatherParticle>>,
+) {
+    let Some(weather) = theme.weather() else {
+        return;
+    };
+    let missing = budget.particles.saturating_sub(query.iter().count());
+    let mut rng = thread_rng();
+    for _ in 0..missing.min(SPAWN_PER_FRAME) {
+        let x = rng.gen_range(-level.width / 2.0..level.width / 2.0);
+        let y = rng.gen_range(-level.height / 2.0..level.height / 2.0);
+        commands.spawn(particle(&weather, Vec2::new(x, y), &mut rng));
+    }
+}
+
+fn particle(
+    weather: &Weather,
+    position: Vec2,
+    rng: &mut impl Rng,
+) -> (WeatherParticle, SpriteBundle) {
+    (
+        WeatherParticle {
+            // not all falling at the same speed looks more natural
+            fall_speed: weather.fall_speed * rng.gen_range(0.8..1.2),
+        },
+        SpriteBundle {
+            transform: Transform {
+                translation: position.extend(WEATHER_Z_INDEX),
+                scale: weather.size.extend(1.0),
+                ..default()
+            },
+            sprite: Sprite {
+                color: weather.color,
+                ..default()
+            },
+            ..default()
+        },
+    )
+}
+
+fn blow_and_fall(
+    time: Res<Time>,
+    theme: Res<Theme>,
+    level: Res<Level>,
+    mut drift: ResMut<WeatherDrift>,
+    wind_query: Query<&GlobalWorldAcceleration, Without<Gravity>>,
+    mut query: Query<(&WeatherParticle, &mut Transform)>,
+) {
+    let Some(weather) = theme.weather() else {
+        return;
+    };
+    // the same wind that pushes the banana, so players can read it off the weather
+    let wind: f32 = wind_query.iter().map(|acc| acc.x).sum();
+    let t = 1.0 - (-DRIFT_EASING * time.delta_seconds()).exp();
+    drift.0 += (wind * weather.wind_drift - drift.0) * t;
+
+    let half = Vec2::new(level.width, level.height) / 2.0;
+    for (particle, mut transform) in query.iter_mut() {
+        let velocity = Vec2::new(drift.0, -particle.fall_speed);
+        transform.translation += (velocity * time.delta_seconds()).extend(0.0);
+        // lean into the wind
+        transform.rotation = Quat::from_rotation_z(velocity.x.atan2(-velocity.y));
+        // start again from the other side once it leaves the arena
+        if transform.translation.y < -half.y {
+            transform.translation.y += level.height;
+        }
+        if transform.translation.x < -half.x {
+            transform.translation.x += level.width;
+        } else if transform.translation.x > half.x {
+            transform.translation.x -= level.width;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn warming_up_is_not_counted() {
+        let mut average = FrameAverage::default();
+        for _ in 0..WARMUP_FRAMES {
+            assert_eq!(average.add(1.0), None);
+        }
+        // starts from the first real frame rather than from nothing
+        assert_eq!(average.add(1.0 / 60.0), Some(1.0 / 60.0));
+    }
+
+    #[test]
+    fn a_single_hitch_is_ignored() {
+        let mut average = FrameAverage::default();
+        let mut budget = WeatherBudget {
+            particles: 100,
+            max: 100,
+        };
+        for frame in 0..100 {
+            let seconds = if frame == 50 { 0.25 } else { 1.0 / 60.0 };
+            if let Some(a) = average.add(seconds) {
+                budget.adjust(a);
+            }
+        }
+        assert_eq!(budget.particles, 100);
+    }
+
+    #[test]
+    fn budget_comes_back_when_frames_are_fast() {
+        let mut budget = WeatherBudget {
+            particles: 100,
+            max: 100,
+        };
+        for _ in 0..10 {
+            budget.adjust(0.1);
+        }
+        assert!(budget.particles < 50);
+        for _ in 0..30 {
+            budget.adjust(1.0 / 60.0);
+        }
+        assert_eq!(budget.particles, 100);
+        // in between it stays where it is
+        budget.particles = 80;
+        budget.adjust(1.0 / 40.0);
+        assert_eq!(budget.particles, 80);
+    }
+}
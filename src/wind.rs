@@ -1,7 +1,12 @@
 use crate::arrow;
+use crate::audio::{PlaySoundEvent, Sound};
+use crate::camera::{setup_camera, view_size, GameCamera};
+use crate::controls::{any_action_just_pressed, GameAction};
+use crate::level::{random_wind, Level};
 use crate::prelude::*;
-use bevy::input::common_conditions::input_just_pressed;
-use rand::{thread_rng, RngCore};
+use crate::theme::{Palette, Theme};
+use bevy::sprite::Anchor;
+use rand::thread_rng;
 
 // Marker component
 #[derive(Component)]
@@ -10,67 +15,97 @@ struct Wind;
 pub(crate) struct WindPlugin;
 impl Plugin for WindPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_wind).add_systems(
-            Update,
-            wind_changer.run_if(input_just_pressed(KeyCode::KeyW)),
-        );
+        app.add_systems(Startup, setup_wind.after(setup_camera))
+            .add_systems(
+                Update,
+                wind_changer.run_if(any_action_just_pressed(GameAction::ChangeWind)),
+            );
     }
 }
 
-fn setup_wind(mut commands: Commands, asset_server: Res<AssetServer>) {
-    spawn_wind_wth_accel(&mut commands, asset_server);
+fn setup_wind(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    level: Res<Level>,
+    theme: Res<Theme>,
+    camera_query: Query<Entity, With<GameCamera>>,
+) {
+    if let Ok(camera) = camera_query.get_single() {
+        spawn_wind_wth_accel(
+            &mut commands,
+            asset_server,
+            &level,
+            &theme.palette(),
+            camera,
+            level.wind,
+        );
+    }
 }
 
-fn spawn_wind_wth_accel(commands: &mut Commands, asset_server: Res<AssetServer>) {
+fn spawn_wind_wth_accel(
+    commands: &mut Commands,
+    asset_server: Res<AssetServer>,
+    level: &Level,
+    palette: &Palette,
+    camera: Entity,
+    wind: i32,
+) {
     let font_medium = asset_server.load("fonts/FiraMono-Medium.ttf");
-    let mut rng = thread_rng();
-    let wind = (rng.next_u32() % 40) as i32 - 20;
     info!("new wind of {}", wind);
     let raw_length = wind as i16 * 10i16;
+    // pinned to the camera so it stays in the top right however the camera moves
     let top = 50.0;
     let right = 300.0;
-    let y = SCREEN_HEIGHT / 2.0 - top;
-    let x = SCREEN_WIDTH / 2.0 - right;
+    let view = view_size(level);
+    let y = view.y / 2.0 - top;
+    let x = view.x / 2.0 - right;
 
-    commands.spawn((
-        Wind,
-        arrow::build_arrow_shape(
-            Color::DARK_GRAY,
-            Color::GRAY,
-            raw_length,
-            30,
-            x,
-            y,
-            WIND_Z_INDEX,
-        ),
-        GlobalWorldAcceleration(Vec2::new(wind as f32, 0.0)),
-    ));
+    commands
+        .spawn((
+            Wind,
+            arrow::build_arrow_shape(
+                palette.wind_outline,
+                palette.wind_fill,
+                raw_length,
+                30,
+                x,
+                y,
+                WIND_Z_INDEX,
+            ),
+            GlobalWorldAcceleration(Vec2::new(wind as f32, 0.0)),
+        ))
+        .set_parent(camera);
 
-    commands.spawn((
-        Wind,
-        wind_text_bundle(font_medium, top - 30.0, right, "wind".to_string()),
-    ));
+    commands
+        .spawn((
+            Wind,
+            wind_text_bundle(font_medium, palette.text, x, y + 30.0, "wind".to_string()),
+        ))
+        .set_parent(camera);
 }
 
-fn wind_text_bundle(font_medium: Handle<Font>, top: f32, right: f32, value: String) -> TextBundle {
-    TextBundle {
+/// text with its top right corner at `x`,`y`
+fn wind_text_bundle(
+    font_medium: Handle<Font>,
+    color: Color,
+    x: f32,
+    y: f32,
+    value: String,
+) -> Text2dBundle {
+    Text2dBundle {
         text: Text {
             sections: vec![TextSection {
                 value,
                 style: TextStyle {
                     font: font_medium,
                     font_size: 30.0,
-                    color: Color::BLACK,
+                    color,
                 },
             }],
             ..default()
         },
-        style: Style {
-            position_type: PositionType::Absolute,
-            top: Val::Px(top),
-            right: Val::Px(right),
-            ..default()
-        },
+        text_anchor: Anchor::TopRight,
+        transform: Transform::from_translation(Vec3::new(x, y, WIND_Z_INDEX)),
         ..default()
     }
 }
@@ -78,10 +113,24 @@ fn wind_text_bundle(font_medium: Handle<Font>, top: f32, right: f32, value: Stri
 fn wind_changer(
     mut commands: Commands,
     asset_server: Res<AssetServer>,
+    level: Res<Level>,
+    theme: Res<Theme>,
     wind_query: Query<Entity, With<Wind>>,
+    camera_query: Query<Entity, With<GameCamera>>,
+    mut sounds: EventWriter<PlaySoundEvent>,
 ) {
+    sounds.send(PlaySoundEvent(Sound::WindChange));
     for we in wind_query.iter() {
-        commands.entity(we).despawn();
+        commands.entity(we).despawn_recursive();
+    }
+    if let Ok(camera) = camera_query.get_single() {
+        spawn_wind_wth_accel(
+            &mut commands,
+            asset_server,
+            &level,
+            &theme.palette(),
+            camera,
+            random_wind(&mut thread_rng()),
+        );
     }
-    spawn_wind_wth_accel(&mut commands, asset_server);
 }